@@ -0,0 +1,91 @@
+use crate::stats::Stats;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Stops a mining run from another thread.
+///
+/// Clones share the same flag, keep one and pass another to
+/// [`crate::GSpanConfigBuilder::cancellation_token`].
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Requests the mining run to stop, it stops before the next pattern is searched.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Why a mining run stopped before the search space was exhausted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    /// The [`CancellationToken`] was cancelled.
+    Cancelled,
+    /// The time limit was reached.
+    TimeLimit,
+    /// The maximum number of patterns was reported.
+    PatternLimit,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StopReason::Cancelled => write!(f, "cancelled"),
+            StopReason::TimeLimit => write!(f, "time limit reached"),
+            StopReason::PatternLimit => write!(f, "pattern limit reached"),
+        }
+    }
+}
+
+/// Result of a mining run, see [`crate::GSpanConfig::run`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunSummary {
+    /// Number of reported patterns.
+    pub patterns: usize,
+    /// The minimum support used, as number of graphs.
+    pub min_sup: usize,
+    /// Set if the run stopped early, the reported patterns are then only a part of all
+    /// frequent subgraphs.
+    pub stop_reason: Option<StopReason>,
+    pub stats: Stats,
+}
+
+impl RunSummary {
+    pub fn is_partial(&self) -> bool {
+        self.stop_reason.is_some()
+    }
+}
+
+/// Limits of a mining run, all of them are optional.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Budget {
+    pub(crate) token: Option<CancellationToken>,
+    pub(crate) time_limit: Option<Duration>,
+    pub(crate) max_patterns: Option<usize>,
+}
+
+impl Budget {
+    /// Returns the reason to stop, if the run was cancelled or the deadline has passed.
+    /// The pattern limit is checked by the caller when the next pattern is found.
+    pub(crate) fn check(&self, deadline: Option<Instant>) -> Option<StopReason> {
+        if self.token.as_ref().is_some_and(|t| t.is_cancelled()) {
+            return Some(StopReason::Cancelled);
+        }
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return Some(StopReason::TimeLimit);
+        }
+        None
+    }
+}
@@ -0,0 +1,554 @@
+use crate::misc::{get_backward, get_forward_pure, get_forward_rm_path, get_forward_root};
+use crate::models::dfs::DFS;
+use crate::models::dfs_code::DFSCode;
+use crate::models::edge::Edge;
+use crate::models::graph::Graph;
+use crate::models::history::History;
+use crate::models::projected::Projected;
+use rustc_hash::FxHashSet;
+use std::collections::BTreeMap;
+
+/// Builds the minimum DFS code of `graph` edge by edge, always choosing the smallest one
+/// edge extension of the code so far.
+///
+/// With a `target`, every further edge is compared to the edge of the target at the same
+/// position and `None` is returned on the first difference, i.e. if the target is not the
+/// minimum code of the graph. Without a target, `None` is returned if the graph has no
+/// edges. The returned code may not cover the whole graph if it is not connected, or if
+/// it is directed and not all edges can be reached along their direction. The edges of
+/// `graph` need the ids assigned by [`Graph::build_edge`].
+pub fn min_dfs_code(graph: &Graph, target: Option<&DFSCode>) -> Option<DFSCode> {
+    let mut code = DFSCode::new();
+    let mut root: BTreeMap<isize, BTreeMap<usize, BTreeMap<isize, Projected>>> = BTreeMap::new();
+    let mut edges: Vec<&Edge> = Vec::with_capacity(32);
+
+    for from in &graph.vertices {
+        if get_forward_root(graph, from, &mut edges) {
+            for it in &edges {
+                let key_1 = from.label;
+                let root_1 = root.entry(key_1).or_default();
+                let key_2 = it.e_label;
+                let root_2 = root_1.entry(key_2).or_default();
+                let key_3 = graph.vertices.get(it.to).unwrap().label;
+                let root_3 = root_2.entry(key_3).or_default();
+                root_3.push(graph.id, it, None);
+            }
+        }
+    }
+
+    let (from_label, mut root_1) = root.pop_first()?;
+    let (e_label, mut root_2) = root_1.pop_first().unwrap();
+    let (to_label, mut projected) = root_2.pop_first().unwrap();
+    // The first edge is not compared: in directed graphs the smallest root edge may not
+    // reach all other edges, the miner then checks the code from its own root edge
+    code.push(0, 1, Some(from_label), e_label, Some(to_label));
+
+    loop {
+        let rm_path = code.build_rm_path();
+        let min_label = code.dfs_vec.first().unwrap().from_label.unwrap();
+        let max_toc: usize = code.dfs_vec.get(*rm_path.first().unwrap()).unwrap().to;
+
+        // Backward edges to the earliest vertex of the rightmost path first
+        let mut root: BTreeMap<usize, Projected> = BTreeMap::new();
+        let mut new_to: usize = 0;
+        for i in (1..rm_path.len()).rev() {
+            for cur in projected.projections.iter() {
+                let history: History = History::build(cur);
+                let e = get_backward(
+                    graph,
+                    history.histories.get(*rm_path.get(i).unwrap()).unwrap(),
+                    history.histories.get(*rm_path.first().unwrap()).unwrap(),
+                    &history,
+                );
+                if let Some(e) = e {
+                    let root_1: &mut Projected = root.entry(e.e_label).or_default();
+                    new_to = code.dfs_vec.get(*rm_path.get(i).unwrap()).unwrap().from;
+                    root_1.push(graph.id, e, Some(cur));
+                }
+            }
+            if !root.is_empty() {
+                break;
+            }
+        }
+        if let Some((e_label, next)) = root.pop_first() {
+            code.push(max_toc, new_to, None, e_label, None);
+            if !matches_target(&code, target) {
+                return None;
+            }
+            projected = next;
+            continue;
+        }
+
+        // Forward edges from the rightmost vertex, then from the rightmost path upwards
+        let mut new_from = 0;
+        let mut root: BTreeMap<usize, BTreeMap<isize, Projected>> = BTreeMap::new();
+        for cur in projected.projections.iter() {
+            let history: History = History::build(cur);
+            if get_forward_pure(
+                graph,
+                history.histories.get(*rm_path.first().unwrap()).unwrap(),
+                min_label,
+                &history,
+                &mut edges,
+            ) {
+                new_from = max_toc;
+                for it in &edges {
+                    let root_1 = root.entry(it.e_label).or_default();
+                    let key_2 = graph.vertices.get(it.to).unwrap().label;
+                    let root_2 = root_1.entry(key_2).or_default();
+                    root_2.push(graph.id, it, Some(cur));
+                }
+            }
+        }
+        if root.is_empty() {
+            for i in 0..rm_path.len() {
+                for cur in projected.projections.iter() {
+                    let history: History = History::build(cur);
+                    if get_forward_rm_path(
+                        graph,
+                        history.histories.get(*rm_path.get(i).unwrap()).unwrap(),
+                        min_label,
+                        &history,
+                        &mut edges,
+                    ) {
+                        new_from = code.dfs_vec.get(*rm_path.get(i).unwrap()).unwrap().from;
+                        for it in &edges {
+                            let root_1 = root.entry(it.e_label).or_default();
+                            let key_2 = graph.vertices.get(it.to).unwrap().label;
+                            let root_2 = root_1.entry(key_2).or_default();
+                            root_2.push(graph.id, it, Some(cur));
+                        }
+                    }
+                }
+                if !root.is_empty() {
+                    break;
+                }
+            }
+        }
+        let Some((e_label, mut root_1)) = root.pop_first() else {
+            return Some(code);
+        };
+        let (to_label, next) = root_1.pop_first().unwrap();
+        code.push(new_from, max_toc + 1, None, e_label, Some(to_label));
+        if !matches_target(&code, target) {
+            return None;
+        }
+        projected = next;
+    }
+}
+
+fn matches_target(code: &DFSCode, target: Option<&DFSCode>) -> bool {
+    let Some(target) = target else {
+        return true;
+    };
+    let index = code.dfs_vec.len() - 1;
+    target.dfs_vec.get(index) == code.dfs_vec.last()
+}
+
+/// Edge of a directed graph as seen from one of its vertices.
+struct HalfEdge {
+    /// The other vertex of the edge.
+    to: usize,
+    e_label: usize,
+    id: usize,
+    /// Whether the edge points to this vertex, i.e. walking it goes against its direction.
+    against: bool,
+}
+
+/// A traversal of the graph which produces the code built so far.
+#[derive(Clone)]
+struct Walk {
+    /// Graph vertex per vertex of the code.
+    vertices: Vec<usize>,
+    /// Vertex of the code per graph vertex, `None` if not visited yet.
+    index: Vec<Option<usize>>,
+    /// Whether the edge with the id is in the code.
+    used: Vec<bool>,
+}
+
+/// Builds the minimum DFS code of a directed graph, which may walk every edge in both
+/// directions, so unlike [`min_dfs_code`] every connected graph without self loops has a
+/// code.
+///
+/// Each edge of the code is ordered as if it went along the traversal, by the order of
+/// [`DFS`], with edges along their direction before edges against it on ties. It is
+/// then written with its actual direction, e.g. `(1,0,2,0,1)` for an edge from the new
+/// vertex 1 to the vertex 0. The edges of `graph` need the ids assigned by
+/// [`Graph::build_edge`]. Returns `None` if the graph has no edges.
+pub fn min_directed_code(graph: &Graph) -> Option<DFSCode> {
+    let mut half_edges: Vec<Vec<HalfEdge>> = graph.vertices.iter().map(|_| Vec::new()).collect();
+    for vertex in &graph.vertices {
+        for edge in &vertex.edges {
+            if edge.from == edge.to {
+                continue;
+            }
+            half_edges[edge.from].push(HalfEdge {
+                to: edge.to,
+                e_label: edge.e_label,
+                id: edge.id,
+                against: false,
+            });
+            half_edges[edge.to].push(HalfEdge {
+                to: edge.from,
+                e_label: edge.e_label,
+                id: edge.id,
+                against: true,
+            });
+        }
+    }
+    let label = |v: usize| graph.vertices[v].label;
+
+    // The first edge, from every vertex
+    let start = Walk {
+        vertices: Vec::new(),
+        index: vec![None; graph.vertices.len()],
+        used: vec![false; graph.edge_size],
+    };
+    let mut best = Candidates::default();
+    for (v, edges) in half_edges.iter().enumerate() {
+        for edge in edges {
+            let dfs = DFS::from(0, 1, Some(label(v)), edge.e_label, Some(label(edge.to)));
+            best.offer((dfs, edge.against), (0, v, edge));
+        }
+    }
+    let (mut key, first) = best.best?;
+    let mut walks: Vec<Walk> = first
+        .into_iter()
+        .map(|(_, v, edge)| {
+            let mut walk = start.clone();
+            walk.index[v] = Some(0);
+            walk.vertices.push(v);
+            walk.extend(edge, true);
+            walk
+        })
+        .collect();
+    let mut code = DFSCode::new();
+    // Parent of every vertex of the code in the depth first search tree
+    let mut parents: Vec<usize> = vec![0, 0];
+
+    loop {
+        code.dfs_vec.push(written(key));
+        let rightmost = parents.len() - 1;
+        let mut rm_path = vec![rightmost];
+        while let Some(&v) = rm_path.last().filter(|v| **v != 0) {
+            rm_path.push(parents[v]);
+        }
+
+        // Backward edges from the rightmost vertex to the rightmost path first
+        let mut best = Candidates::default();
+        for (w, walk) in walks.iter().enumerate() {
+            let r = walk.vertices[rightmost];
+            for edge in &half_edges[r] {
+                if walk.used[edge.id] {
+                    continue;
+                }
+                if let Some(to) = walk.index[edge.to].filter(|to| rm_path.contains(to)) {
+                    let dfs = DFS::from(rightmost, to, None, edge.e_label, None);
+                    best.offer((dfs, edge.against), (w, r, edge));
+                }
+            }
+        }
+        // Forward edges from the rightmost path
+        if best.best.is_none() {
+            for (w, walk) in walks.iter().enumerate() {
+                for &from in &rm_path {
+                    let v = walk.vertices[from];
+                    for edge in &half_edges[v] {
+                        if walk.used[edge.id] || walk.index[edge.to].is_some() {
+                            continue;
+                        }
+                        let dfs = DFS::from(
+                            from,
+                            rightmost + 1,
+                            None,
+                            edge.e_label,
+                            Some(label(edge.to)),
+                        );
+                        best.offer((dfs, edge.against), (w, v, edge));
+                    }
+                }
+            }
+        }
+
+        let Some((next, extensions)) = best.best else {
+            return Some(code);
+        };
+        let forward = !next.0.is_backward();
+        if forward {
+            parents.push(next.0.from);
+        }
+        walks = extensions
+            .into_iter()
+            .map(|(w, _, edge)| {
+                let mut walk = walks[w].clone();
+                walk.extend(edge, forward);
+                walk
+            })
+            .collect();
+        key = next;
+    }
+}
+
+impl Walk {
+    /// Adds the edge to the code, and its other vertex if it is new.
+    fn extend(&mut self, edge: &HalfEdge, forward: bool) {
+        self.used[edge.id] = true;
+        if forward {
+            self.index[edge.to] = Some(self.vertices.len());
+            self.vertices.push(edge.to);
+        }
+    }
+}
+
+/// An edge of the code as walked by the traversal, and whether it points against it.
+type Key = (DFS, bool);
+
+/// Index of a walk, the vertex an edge is walked from and the edge.
+type Extension<'a> = (usize, usize, &'a HalfEdge);
+
+/// The smallest edge offered so far with the walks producing it.
+#[derive(Default)]
+struct Candidates<'a> {
+    best: Option<(Key, Vec<Extension<'a>>)>,
+}
+
+impl<'a> Candidates<'a> {
+    fn offer(&mut self, key: Key, extension: Extension<'a>) {
+        match &mut self.best {
+            Some((best, extensions)) if *best == key => extensions.push(extension),
+            Some((best, _)) if *best < key => {}
+            _ => self.best = Some((key, vec![extension])),
+        }
+    }
+}
+
+/// The edge of the code for an edge walked along the traversal, reversed if the edge
+/// points against it.
+fn written((dfs, against): Key) -> DFS {
+    if against {
+        DFS::from(dfs.to, dfs.from, dfs.to_label, dfs.e_label, dfs.from_label)
+    } else {
+        dfs
+    }
+}
+
+/// Whether the search of the miner reaches the whole graph, i.e. visits a DFS code with
+/// all of its edges which passes the minimality check at every step. Directed codes
+/// only follow the edge directions, so not every directed graph is reached. The edges of
+/// `graph` need the ids assigned by [`Graph::build_edge`].
+///
+/// Unlike mining the graph, only codes which can still cover the whole graph are
+/// followed: a vertex which left the rightmost path never gets further edges. Such a code
+/// starts at a vertex with the smallest label and only adds the edges of its tree as
+/// forward edges, see [`code_tree`].
+pub(crate) fn is_reachable(graph: &Graph) -> bool {
+    let mut incoming: Vec<Vec<usize>> = vec![Vec::new(); graph.vertices.len()];
+    for edge in graph.vertices.iter().flat_map(|vertex| &vertex.edges) {
+        incoming[edge.to].push(edge.id);
+    }
+    let mut search = Search {
+        graph,
+        incoming: &incoming,
+        tree: Vec::new(),
+        code: DFSCode::new(),
+        histories: Vec::new(),
+        vertices: Vec::new(),
+        used: FxHashSet::default(),
+        is_min: BTreeMap::new(),
+    };
+    let min_label = graph.vertices.iter().map(|vertex| vertex.label).min();
+    let mut edges: Vec<&Edge> = Vec::new();
+    graph.vertices.iter().any(|from| {
+        if Some(from.label) != min_label {
+            return false;
+        }
+        let Some(tree) = code_tree(graph, from.id) else {
+            return false;
+        };
+        search.tree = tree;
+        get_forward_root(graph, from, &mut edges);
+        edges.iter().any(|edge| {
+            if search.tree[edge.to] != Some(edge.id) {
+                return false;
+            }
+            let to_label = graph.vertices[edge.to].label;
+            let dfs = DFS::from(0, 1, Some(from.label), edge.e_label, Some(to_label));
+            search.vertices.push(edge.from);
+            let found = search.visit(dfs, edge);
+            search.vertices.pop();
+            found
+        })
+    })
+}
+
+/// The id of the forward edge to every vertex of a directed DFS code starting at `root`
+/// which has all edges of the graph, `None` if there is no such code.
+///
+/// Such a code is a depth first search tree along the edge directions in which every
+/// other edge points to an ancestor of its vertex, so the tree is the dominator tree of
+/// the root: every incoming edge of a vertex comes from a vertex it dominates, except
+/// exactly one from its immediate dominator.
+fn code_tree(graph: &Graph, root: usize) -> Option<Vec<Option<usize>>> {
+    let n = graph.vertices.len();
+    // Vertices reachable from the root without passing the skipped vertex
+    let reach = |skip: usize| {
+        let mut seen = vec![false; n];
+        seen[root] = true;
+        let mut stack = vec![root];
+        while let Some(v) = stack.pop() {
+            for edge in &graph.vertices[v].edges {
+                if !seen[edge.to] && edge.to != skip {
+                    seen[edge.to] = true;
+                    stack.push(edge.to);
+                }
+            }
+        }
+        seen
+    };
+    if reach(usize::MAX).contains(&false) {
+        return None;
+    }
+    // Whether the first vertex dominates the second
+    let dominates: Vec<Vec<bool>> = (0..n)
+        .map(|d| match d == root {
+            true => vec![true; n],
+            false => reach(d).into_iter().map(|seen| !seen).collect(),
+        })
+        .collect();
+    let mut tree: Vec<Option<usize>> = vec![None; n];
+    let complete = graph
+        .vertices
+        .iter()
+        .flat_map(|vertex| &vertex.edges)
+        .all(|edge| {
+            let (from, to) = (edge.from, edge.to);
+            if dominates[to][from] {
+                return true;
+            }
+            let immediate = dominates[from][to]
+                && (0..n).all(|d| d == to || !dominates[d][to] || dominates[d][from]);
+            // Parallel edges with the same label share their id
+            immediate && *tree[to].get_or_insert(edge.id) == edge.id
+        });
+    complete.then_some(tree)
+}
+
+/// One embedding of a DFS code in the graph of [`is_reachable`].
+struct Search<'a> {
+    graph: &'a Graph,
+    /// Ids of the incoming edges per vertex.
+    incoming: &'a [Vec<usize>],
+    /// Id of the forward edge to every vertex, see [`code_tree`].
+    tree: Vec<Option<usize>>,
+    code: DFSCode,
+    /// Graph edge per edge of the code.
+    histories: Vec<&'a Edge>,
+    /// Graph vertex per vertex of the code.
+    vertices: Vec<usize>,
+    /// Ids of the graph edges in the code.
+    used: FxHashSet<usize>,
+    /// Result of the minimality check per code, the same code occurs in several
+    /// embeddings.
+    is_min: BTreeMap<DFSCode, bool>,
+}
+
+impl<'a> Search<'a> {
+    /// Adds the edge to the code and searches all extensions the miner would visit,
+    /// returns true once all edges of the graph are in the code.
+    fn visit(&mut self, dfs: DFS, edge: &'a Edge) -> bool {
+        let forward = !dfs.is_backward();
+        self.code.dfs_vec.push(dfs);
+        self.histories.push(edge);
+        self.used.insert(edge.id);
+        if forward {
+            self.vertices.push(edge.to);
+        }
+        let found = if self.used.len() == self.graph.edge_size {
+            self.is_min()
+        } else {
+            self.can_complete() && self.is_min() && self.visit_children()
+        };
+        self.code.dfs_vec.pop();
+        self.histories.pop();
+        self.used.remove(&edge.id);
+        if forward {
+            self.vertices.pop();
+        }
+        found
+    }
+
+    /// Whether all edges of the vertices off the rightmost path are in the code.
+    fn can_complete(&self) -> bool {
+        let rm_path = self.code.build_rm_path();
+        let mut on_path = vec![false; self.vertices.len()];
+        on_path[self.vertices.len() - 1] = true;
+        for &i in &rm_path {
+            on_path[self.code.dfs_vec[i].from] = true;
+        }
+        self.vertices.iter().zip(on_path).all(|(&v, on_path)| {
+            on_path
+                || self.graph.vertices[v]
+                    .edges
+                    .iter()
+                    .map(|edge| edge.id)
+                    .chain(self.incoming[v].iter().copied())
+                    .all(|id| self.used.contains(&id))
+        })
+    }
+
+    /// The minimality check of the miner.
+    fn is_min(&mut self) -> bool {
+        if self.code.dfs_vec.len() == 1 {
+            return true;
+        }
+        if let Some(&is_min) = self.is_min.get(&self.code) {
+            return is_min;
+        }
+        let mut g = Graph::new(0, self.graph.directed);
+        self.code.to_graph(&mut g, false);
+        let is_min = min_dfs_code(&g, Some(&self.code)).is_some();
+        self.is_min.insert(self.code.clone(), is_min);
+        is_min
+    }
+
+    /// Visits the extensions of the code in this embedding, like the miner does.
+    fn visit_children(&mut self) -> bool {
+        let graph = self.graph;
+        let rm_path = self.code.build_rm_path();
+        let min_label = self.code.dfs_vec[0].from_label.unwrap();
+        let max_toc = self.code.dfs_vec[rm_path[0]].to;
+        let history = History {
+            histories: self.histories.clone(),
+            edges: self.used.clone(),
+            vertices: self.vertices.iter().copied().collect(),
+        };
+        let mut children: Vec<(DFS, &'a Edge)> = Vec::new();
+        for i in (1..rm_path.len()).rev() {
+            let e1 = self.histories[rm_path[i]];
+            if let Some(e) = get_backward(graph, e1, self.histories[rm_path[0]], &history) {
+                let to = self.code.dfs_vec[rm_path[i]].from;
+                children.push((DFS::from(max_toc, to, None, e.e_label, None), e));
+            }
+        }
+        let mut edges: Vec<&Edge> = Vec::new();
+        let last = self.histories[rm_path[0]];
+        get_forward_pure(graph, last, min_label, &history, &mut edges);
+        let mut forward: Vec<(usize, &Edge)> = edges.iter().map(|&e| (max_toc, e)).collect();
+        for &i in &rm_path {
+            get_forward_rm_path(graph, self.histories[i], min_label, &history, &mut edges);
+            let from = self.code.dfs_vec[i].from;
+            forward.extend(edges.iter().map(|&e| (from, e)));
+        }
+        for (from, e) in forward {
+            if self.tree[e.to] != Some(e.id) {
+                continue;
+            }
+            let to_label = graph.vertices[e.to].label;
+            let dfs = DFS::from(from, max_toc + 1, None, e.e_label, Some(to_label));
+            children.push((dfs, e));
+        }
+        children
+            .into_iter()
+            .any(|(dfs, edge)| self.visit(dfs, edge))
+    }
+}
@@ -0,0 +1,437 @@
+use crate::budget::{Budget, CancellationToken};
+use crate::gspan::GSpanConfig;
+#[cfg(doc)]
+use crate::models::dfs_code::DFSCode;
+use crate::models::graph::Graph;
+use crate::models::labels::LabelDictionary;
+use crate::progress::{Progress, ProgressHook};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Minimum support of a pattern, see [`GSpanConfigBuilder::support`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Support {
+    /// Number of graphs.
+    Absolute(usize),
+    /// Fraction of the graphs of the database, in `(0, 1]`.
+    Relative(f64),
+}
+
+impl Support {
+    /// The number of graphs for a database of `graphs` graphs, relative supports are
+    /// rounded up and at least 1.
+    pub fn resolve(&self, graphs: usize) -> usize {
+        match *self {
+            Support::Absolute(count) => count,
+            // Tolerate rounding errors like 0.07 * 100 = 7.000000000000001
+            Support::Relative(fraction) => {
+                ((fraction * graphs as f64 - 1e-9).ceil() as usize).max(1)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Support {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Support::Absolute(count) => write!(f, "{}", count),
+            Support::Relative(fraction) => write!(f, "{}", fraction),
+        }
+    }
+}
+
+/// Error of parsing a [`Support`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SupportParseError(pub String);
+
+impl fmt::Display for SupportParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid support: {}", self.0)
+    }
+}
+
+impl std::error::Error for SupportParseError {}
+
+impl FromStr for Support {
+    type Err = SupportParseError;
+
+    /// Parses a number of graphs (`10`), a percentage (`5%`) or a fraction (`0.05`).
+    fn from_str(s: &str) -> Result<Support, SupportParseError> {
+        let s = s.trim();
+        if let Ok(count) = s.parse::<usize>() {
+            return Ok(Support::Absolute(count));
+        }
+        let (number, scale) = match s.strip_suffix('%') {
+            Some(number) => (number.trim(), 100.0),
+            None => (s, 1.0),
+        };
+        let fraction = number
+            .parse::<f64>()
+            .map_err(|_| SupportParseError(format!("'{}' is not a number or percentage", s)))?
+            / scale;
+        if !(fraction > 0.0 && fraction <= 1.0) {
+            return Err(SupportParseError(format!(
+                "'{}' is not between 0 and 100%",
+                s
+            )));
+        }
+        Ok(Support::Relative(fraction))
+    }
+}
+
+/// Which of the frequent subgraphs are reported.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MiningMode {
+    /// All frequent subgraphs.
+    #[default]
+    All,
+    /// Only closed subgraphs, which have no supergraph with one more edge and the same
+    /// support (CloseGraph).
+    Closed,
+    /// Only maximal subgraphs, which have no frequent supergraph with one more edge.
+    Maximal,
+}
+
+/// Invalid settings detected by [`GSpanConfigBuilder::build`].
+#[derive(Debug, PartialEq)]
+pub enum ConfigError {
+    /// The minimum support has to be at least 1.
+    ZeroSupport,
+    /// A relative minimum support is not in `(0, 1]`.
+    InvalidRelativeSupport(f64),
+    /// The minimum number of vertices is larger than the maximum number of vertices.
+    InvalidVertexRange { min: usize, max: usize },
+    /// A graph of the database has a different `directed` flag than the configuration.
+    DirectedMismatch { graph_id: usize, directed: bool },
+    /// The graph at the given position of the database does not have its position as id.
+    InvalidGraphId { position: usize, graph_id: usize },
+    /// An embeddings file was set, but collecting the embeddings was not enabled.
+    EmbeddingsNotEnabled,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::ZeroSupport => write!(f, "Min support must be at least 1"),
+            ConfigError::InvalidRelativeSupport(fraction) => write!(
+                f,
+                "Relative min support must be in (0, 1], but is {}",
+                fraction
+            ),
+            ConfigError::InvalidVertexRange { min, max } => write!(
+                f,
+                "Minimum number of vertices ({}) is larger than the maximum number of vertices ({})",
+                min, max
+            ),
+            ConfigError::DirectedMismatch { graph_id, directed } => write!(
+                f,
+                "Graph {} is {}, but the configuration expects {} graphs",
+                graph_id,
+                if *directed { "directed" } else { "undirected" },
+                if *directed { "undirected" } else { "directed" }
+            ),
+            ConfigError::InvalidGraphId { position, graph_id } => write!(
+                f,
+                "Graph at position {} has the id {}, graph ids must match their position",
+                position, graph_id
+            ),
+            ConfigError::EmbeddingsNotEnabled => write!(
+                f,
+                "An embeddings file requires a maximum number of embeddings per graph"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Builder for [`GSpanConfig`] with the same defaults as the command line tool.
+///
+/// ```
+/// use gspan::{GSpanConfig, Graph};
+///
+/// let graphs: Vec<Graph> = Vec::new();
+/// let gspan = GSpanConfig::builder(graphs)
+///     .min_sup(2)
+///     .min_vertices(1)
+///     .max_vertices(5)
+///     .build()
+///     .unwrap();
+/// ```
+pub struct GSpanConfigBuilder {
+    graphs: Vec<Graph>,
+    min_sup: Support,
+    min_vertices: usize,
+    max_vertices: usize,
+    directed: bool,
+    single_nodes: bool,
+    mode: MiningMode,
+    out_path: String,
+    labels: Option<LabelDictionary>,
+    graph_ids: bool,
+    embeddings: Option<usize>,
+    embeddings_path: Option<String>,
+    budget: Budget,
+    progress: Option<ProgressHook>,
+}
+
+impl GSpanConfigBuilder {
+    pub fn new(graphs: Vec<Graph>) -> GSpanConfigBuilder {
+        GSpanConfigBuilder {
+            graphs,
+            min_sup: Support::Absolute(2),
+            min_vertices: 1,
+            max_vertices: 10,
+            directed: false,
+            single_nodes: false,
+            mode: MiningMode::All,
+            out_path: "out.txt".to_string(),
+            labels: None,
+            graph_ids: false,
+            embeddings: None,
+            embeddings_path: None,
+            budget: Budget::default(),
+            progress: None,
+        }
+    }
+
+    /// Minimum number of graphs a pattern has to occur in (default: 2).
+    pub fn min_sup(mut self, min_sup: usize) -> GSpanConfigBuilder {
+        self.min_sup = Support::Absolute(min_sup);
+        self
+    }
+
+    /// Minimum support as number of graphs or relative to the number of graphs, like
+    /// [`GSpanConfigBuilder::min_sup`]. The resolved number of graphs is returned by
+    /// [`GSpanConfig::min_sup`].
+    pub fn support(mut self, support: Support) -> GSpanConfigBuilder {
+        self.min_sup = support;
+        self
+    }
+
+    /// Minimum number of vertices of a reported pattern (default: 1).
+    pub fn min_vertices(mut self, min_vertices: usize) -> GSpanConfigBuilder {
+        self.min_vertices = min_vertices;
+        self
+    }
+
+    /// Maximum number of vertices of a reported pattern (default: 10).
+    pub fn max_vertices(mut self, max_vertices: usize) -> GSpanConfigBuilder {
+        self.max_vertices = max_vertices;
+        self
+    }
+
+    /// Whether the graphs are directed (default: false).
+    pub fn directed(mut self, directed: bool) -> GSpanConfigBuilder {
+        self.directed = directed;
+        self
+    }
+
+    /// Whether vertices with the same label are merged in the reported patterns (default: false).
+    ///
+    /// Merging only changes how a pattern is written, see [`DFSCode::to_graph`]. Mining,
+    /// the support, the vertex limits and the minimality check use the unmerged pattern,
+    /// so different patterns may be reported as the same merged graph.
+    pub fn single_nodes(mut self, single_nodes: bool) -> GSpanConfigBuilder {
+        self.single_nodes = single_nodes;
+        self
+    }
+
+    /// Which frequent subgraphs are reported (default: [`MiningMode::All`]).
+    ///
+    /// In closed and maximal mode, a pattern is checked against all its one edge
+    /// extensions within the vertex limits, also those searched in other branches. In
+    /// closed mode, the extensions of a pattern are not searched if none of them can be
+    /// closed. For directed graphs, only supergraphs which the search can reach along the
+    /// edge directions are taken into account, and the search is not cut short.
+    pub fn mode(mut self, mode: MiningMode) -> GSpanConfigBuilder {
+        self.mode = mode;
+        self
+    }
+
+    /// Output file used by [`GSpanConfig::run`] (default: `out.txt`).
+    pub fn out_path<S: Into<String>>(mut self, out_path: S) -> GSpanConfigBuilder {
+        self.out_path = out_path.into();
+        self
+    }
+
+    /// String labels the graphs were parsed with, [`GSpanConfig::run`] writes the
+    /// patterns with these labels (default: none, integer labels are written).
+    pub fn labels(mut self, labels: LabelDictionary) -> GSpanConfigBuilder {
+        self.labels = Some(labels);
+        self
+    }
+
+    /// Whether [`GSpanConfig::run`] writes an `x:` line with the original ids of the
+    /// supporting graphs after each pattern (default: `false`).
+    pub fn graph_ids(mut self, graph_ids: bool) -> GSpanConfigBuilder {
+        self.graph_ids = graph_ids;
+        self
+    }
+
+    /// Collects up to `max_per_graph` embeddings per supporting graph into
+    /// [`crate::Pattern::embeddings`] (default: none are collected).
+    pub fn embeddings(mut self, max_per_graph: usize) -> GSpanConfigBuilder {
+        self.embeddings = Some(max_per_graph);
+        self
+    }
+
+    /// File to which [`GSpanConfig::run`] writes the embeddings, see
+    /// [`crate::sink::EmbeddingSink`] (default: none). Requires
+    /// [`GSpanConfigBuilder::embeddings`].
+    pub fn embeddings_path<S: Into<String>>(mut self, path: S) -> GSpanConfigBuilder {
+        self.embeddings_path = Some(path.into());
+        self
+    }
+
+    /// Token to stop the mining runs from another thread (default: none).
+    pub fn cancellation_token(mut self, token: CancellationToken) -> GSpanConfigBuilder {
+        self.budget.token = Some(token);
+        self
+    }
+
+    /// Wall-clock time after which a mining run stops (default: no limit).
+    pub fn time_limit(mut self, time_limit: Duration) -> GSpanConfigBuilder {
+        self.budget.time_limit = Some(time_limit);
+        self
+    }
+
+    /// Number of patterns after which a mining run stops (default: no limit).
+    pub fn max_patterns(mut self, max_patterns: usize) -> GSpanConfigBuilder {
+        self.budget.max_patterns = Some(max_patterns);
+        self
+    }
+
+    /// Hook called with the [`Progress`] of the search after every visited one edge
+    /// extension (default: none). It is called often, so it should return quickly and
+    /// throttle any output itself.
+    pub fn progress<F>(mut self, hook: F) -> GSpanConfigBuilder
+    where
+        F: Fn(&Progress) + Send + Sync + 'static,
+    {
+        self.progress = Some(Arc::new(hook));
+        self
+    }
+
+    pub fn build(self) -> Result<GSpanConfig, ConfigError> {
+        if let Support::Relative(fraction) = self.min_sup {
+            if !(fraction > 0.0 && fraction <= 1.0) {
+                return Err(ConfigError::InvalidRelativeSupport(fraction));
+            }
+        }
+        let min_sup = self.min_sup.resolve(self.graphs.len());
+        if min_sup == 0 {
+            return Err(ConfigError::ZeroSupport);
+        }
+        if self.min_vertices > self.max_vertices {
+            return Err(ConfigError::InvalidVertexRange {
+                min: self.min_vertices,
+                max: self.max_vertices,
+            });
+        }
+        if self.embeddings_path.is_some() && self.embeddings.is_none() {
+            return Err(ConfigError::EmbeddingsNotEnabled);
+        }
+        for (position, graph) in self.graphs.iter().enumerate() {
+            if graph.id != position {
+                return Err(ConfigError::InvalidGraphId {
+                    position,
+                    graph_id: graph.id,
+                });
+            }
+            if graph.directed != self.directed {
+                return Err(ConfigError::DirectedMismatch {
+                    graph_id: graph.id,
+                    directed: graph.directed,
+                });
+            }
+        }
+        let mut config = GSpanConfig::new(
+            self.graphs,
+            min_sup,
+            self.min_vertices,
+            self.max_vertices,
+            self.directed,
+            self.single_nodes,
+            self.out_path,
+        );
+        config.set_mode(self.mode);
+        config.labels = self.labels;
+        config.graph_ids = self.graph_ids;
+        config.embeddings = self.embeddings;
+        config.embeddings_path = self.embeddings_path;
+        config.budget = self.budget;
+        config.progress = self.progress;
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graphs(directed: bool) -> Vec<Graph> {
+        vec![Graph::new(0, directed), Graph::new(1, directed)]
+    }
+
+    #[test]
+    fn test_build_defaults() {
+        assert!(GSpanConfigBuilder::new(graphs(false)).build().is_ok());
+    }
+
+    #[test]
+    fn test_build_invalid() {
+        let err = GSpanConfigBuilder::new(graphs(false)).min_sup(0).build();
+        assert_eq!(err.err(), Some(ConfigError::ZeroSupport));
+        let err = GSpanConfigBuilder::new(graphs(false))
+            .support(Support::Relative(1.5))
+            .build();
+        assert_eq!(err.err(), Some(ConfigError::InvalidRelativeSupport(1.5)));
+        let err = GSpanConfigBuilder::new(graphs(false))
+            .min_vertices(5)
+            .max_vertices(3)
+            .build();
+        assert_eq!(
+            err.err(),
+            Some(ConfigError::InvalidVertexRange { min: 5, max: 3 })
+        );
+        let err = GSpanConfigBuilder::new(graphs(true)).build();
+        assert_eq!(
+            err.err(),
+            Some(ConfigError::DirectedMismatch {
+                graph_id: 0,
+                directed: true
+            })
+        );
+        let err = GSpanConfigBuilder::new(vec![Graph::new(1, false)]).build();
+        assert_eq!(
+            err.err(),
+            Some(ConfigError::InvalidGraphId {
+                position: 0,
+                graph_id: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_support() {
+        assert_eq!("10".parse(), Ok(Support::Absolute(10)));
+        assert_eq!("5%".parse(), Ok(Support::Relative(0.05)));
+        assert_eq!("0.05".parse(), Ok(Support::Relative(0.05)));
+        assert!("0%".parse::<Support>().is_err());
+        assert!("1.5".parse::<Support>().is_err());
+        assert!("five".parse::<Support>().is_err());
+
+        assert_eq!(Support::Relative(0.05).resolve(200), 10);
+        assert_eq!(Support::Relative(0.07).resolve(100), 7);
+        assert_eq!(Support::Relative(0.05).resolve(210), 11);
+        assert_eq!(Support::Relative(0.01).resolve(10), 1);
+        let config = GSpanConfigBuilder::new(graphs(false))
+            .support(Support::Relative(0.5))
+            .build()
+            .unwrap();
+        assert_eq!(config.min_sup(), 1);
+    }
+}
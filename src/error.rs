@@ -0,0 +1,116 @@
+use crate::config::ConfigError;
+use crate::models::graph::GraphError;
+use crate::parser::GraphSetParseError;
+use std::{fmt, io};
+
+/// Errors returned by the library.
+#[derive(Debug)]
+pub enum Error {
+    /// Reading the input graph database failed.
+    Io(io::Error),
+    /// The input graph database is malformed, holds at least one error.
+    Parse(Vec<GraphSetParseError>),
+    /// A graph could not be constructed.
+    Graph(GraphError),
+    /// The mining configuration is invalid.
+    Config(ConfigError),
+    /// Writing the mined patterns failed.
+    Output(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "Error reading input: {}", err),
+            Error::Parse(errors) => {
+                write!(f, "Error parsing input:")?;
+                for err in errors {
+                    write!(f, "\n{}", err)?;
+                }
+                Ok(())
+            }
+            Error::Graph(err) => write!(f, "Invalid graph: {}", err),
+            Error::Config(err) => write!(f, "Invalid configuration: {}", err),
+            Error::Output(err) => write!(f, "Error writing output: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) | Error::Output(err) => Some(err),
+            Error::Parse(errors) => errors
+                .first()
+                .map(|err| err as &(dyn std::error::Error + 'static)),
+            Error::Graph(err) => Some(err),
+            Error::Config(err) => Some(err),
+        }
+    }
+}
+
+impl From<GraphSetParseError> for Error {
+    fn from(err: GraphSetParseError) -> Error {
+        Error::Parse(vec![err])
+    }
+}
+
+impl From<ConfigError> for Error {
+    fn from(err: ConfigError) -> Error {
+        Error::Config(err)
+    }
+}
+
+impl From<GraphError> for Error {
+    fn from(err: GraphError) -> Error {
+        Error::Graph(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GSpanConfig, Graph, GraphSetParser};
+
+    #[test]
+    fn test_error_kinds() {
+        let missing = std::env::temp_dir()
+            .join("gspan-missing-dir")
+            .join("in.txt");
+        let err = Graph::graphs_set_from_file(&missing, false).unwrap_err();
+        assert!(matches!(err, Error::Io(_)));
+
+        let result = GSpanConfig::builder(Vec::new()).min_sup(0).build();
+        assert!(matches!(
+            result.map_err(Error::from),
+            Err(Error::Config(ConfigError::ZeroSupport))
+        ));
+
+        let gspan = GSpanConfig::builder(Vec::new())
+            .out_path(missing.display().to_string())
+            .build()
+            .unwrap();
+        let err = gspan.run().unwrap_err();
+        assert!(matches!(err, Error::Output(_)));
+        assert!(std::error::Error::source(&err).is_some());
+    }
+
+    #[test]
+    fn test_display() {
+        let err = Error::from(ConfigError::ZeroSupport);
+        assert_eq!(
+            err.to_string(),
+            "Invalid configuration: Min support must be at least 1"
+        );
+        let err = GraphSetParser::new(false)
+            .collect_errors(true)
+            .parse_reader("t # 0\nv 0 x\nv 1 y\n".as_bytes(), "test")
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Error parsing input:\n\
+             test:2:5: Invalid vertex label 'x'\n    v 0 x\n        ^\n\
+             test:3:5: Invalid vertex label 'y'\n    v 1 y\n        ^"
+        );
+    }
+}
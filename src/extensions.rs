@@ -0,0 +1,162 @@
+use crate::models::edge::Edge;
+use crate::models::graph::Graph;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+/// One edge extension of a pattern, in terms of the pattern vertices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Extension {
+    /// Edge between two pattern vertices, with `from < to` for undirected graphs.
+    Inner {
+        from: usize,
+        to: usize,
+        e_label: usize,
+    },
+    /// Edge between a pattern vertex and a new vertex with the given label, `incoming`
+    /// if it points to the pattern vertex.
+    Outer {
+        vertex: usize,
+        e_label: usize,
+        label: isize,
+        incoming: bool,
+    },
+}
+
+impl Extension {
+    /// Adds the edge (and the new vertex) to the pattern graph.
+    pub(crate) fn apply(&self, graph: &mut Graph) {
+        let result = match *self {
+            Extension::Inner { from, to, e_label } => graph.add_edge(from, to, e_label),
+            Extension::Outer {
+                vertex,
+                e_label,
+                label,
+                incoming,
+            } => {
+                let new = graph.add_vertex(label);
+                if incoming {
+                    graph.add_edge(new, vertex, e_label)
+                } else {
+                    graph.add_edge(vertex, new, e_label)
+                }
+            }
+        };
+        result.expect("extension of a vertex of the pattern");
+    }
+}
+
+/// How often an extension occurs.
+#[derive(Debug, Default)]
+pub(crate) struct Occurrences {
+    /// Number of graphs with an embedding which has the extension.
+    pub(crate) graphs: usize,
+    /// Number of embeddings which have the extension.
+    pub(crate) embeddings: usize,
+    last_graph: Option<usize>,
+}
+
+/// An occurrence of a pattern in a graph of the database.
+pub(crate) struct Embedding {
+    /// Index of the graph in the database.
+    pub(crate) graph: usize,
+    /// Graph vertex per pattern vertex.
+    pub(crate) vertices: Vec<usize>,
+    /// Ids of the graph edges used by the pattern.
+    pub(crate) edges: FxHashSet<usize>,
+}
+
+/// All one edge extensions of the embeddings of a pattern, not only the rightmost ones
+/// visited by the search. Self loops are left out as the miner skips them.
+pub(crate) struct Extensions {
+    /// Number of embeddings of the pattern.
+    pub(crate) embeddings: usize,
+    pub(crate) occurrences: FxHashMap<Extension, Occurrences>,
+}
+
+impl Extensions {
+    /// Collects the extensions of the embeddings, which have to be grouped by graph.
+    /// `incoming` holds the incoming edges per vertex of every graph if they are
+    /// directed, see [`incoming_edges`].
+    pub(crate) fn collect<I>(
+        graphs: &[Graph],
+        incoming: &[Vec<Vec<Edge>>],
+        embeddings: I,
+    ) -> Extensions
+    where
+        I: IntoIterator<Item = Embedding>,
+    {
+        let mut extensions = Extensions {
+            embeddings: 0,
+            occurrences: FxHashMap::default(),
+        };
+        let mut found: FxHashSet<Extension> = FxHashSet::default();
+        for embedding in embeddings {
+            let graph = &graphs[embedding.graph];
+            let pattern_vertex: FxHashMap<usize, usize> = embedding
+                .vertices
+                .iter()
+                .enumerate()
+                .map(|(p, v)| (*v, p))
+                .collect();
+            for (p, &v) in embedding.vertices.iter().enumerate() {
+                for edge in &graph.vertices[v].edges {
+                    if edge.to == v || embedding.edges.contains(&edge.id) {
+                        continue;
+                    }
+                    found.insert(match pattern_vertex.get(&edge.to) {
+                        Some(&q) => Extension::Inner {
+                            from: if graph.directed { p } else { p.min(q) },
+                            to: if graph.directed { q } else { p.max(q) },
+                            e_label: edge.e_label,
+                        },
+                        None => Extension::Outer {
+                            vertex: p,
+                            e_label: edge.e_label,
+                            label: graph.vertices[edge.to].label,
+                            incoming: false,
+                        },
+                    });
+                }
+                if !graph.directed {
+                    continue;
+                }
+                // Edges between pattern vertices are found as outgoing edges above
+                for edge in &incoming[embedding.graph][v] {
+                    if !pattern_vertex.contains_key(&edge.from) {
+                        found.insert(Extension::Outer {
+                            vertex: p,
+                            e_label: edge.e_label,
+                            label: graph.vertices[edge.from].label,
+                            incoming: true,
+                        });
+                    }
+                }
+            }
+            extensions.embeddings += 1;
+            for extension in found.drain() {
+                let occurrences = extensions.occurrences.entry(extension).or_default();
+                occurrences.embeddings += 1;
+                if occurrences.last_graph != Some(embedding.graph) {
+                    occurrences.last_graph = Some(embedding.graph);
+                    occurrences.graphs += 1;
+                }
+            }
+        }
+        extensions
+    }
+}
+
+/// Incoming edges per vertex of every graph, indexed like [`Graph::vertices`].
+pub(crate) fn incoming_edges(graphs: &[Graph]) -> Vec<Vec<Vec<Edge>>> {
+    graphs
+        .iter()
+        .map(|graph| {
+            let mut incoming: Vec<Vec<Edge>> = vec![Vec::new(); graph.vertices.len()];
+            for vertex in &graph.vertices {
+                for edge in &vertex.edges {
+                    incoming[edge.to].push(edge.clone());
+                }
+            }
+            incoming
+        })
+        .collect()
+}
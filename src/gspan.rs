@@ -1,12 +1,23 @@
+use crate::budget::{Budget, RunSummary, StopReason};
+use crate::canonical::{is_reachable, min_dfs_code};
+use crate::config::{GSpanConfigBuilder, MiningMode};
+use crate::error::Error;
+use crate::extensions::{incoming_edges, Embedding, Extension, Extensions};
+use crate::matcher::Match;
 use crate::misc::{get_backward, get_forward_pure, get_forward_rm_path, get_forward_root};
+use crate::models::dfs::DFS;
 use crate::models::dfs_code::DFSCode;
 use crate::models::edge::Edge;
 use crate::models::graph::Graph;
 use crate::models::history::History;
+use crate::models::labels::LabelDictionary;
+use crate::models::pattern::Pattern;
 use crate::models::projected::Projected;
+use crate::progress::{Progress, ProgressHook};
+use crate::sink::{EmbeddingSink, PatternSink, TextSink};
+use crate::stats::Stats;
 use std::collections::BTreeMap;
-use std::fs::File;
-use std::io::{BufWriter, Write};
+use std::time::Instant;
 
 pub struct GSpanConfig {
     out_path: String,
@@ -16,9 +27,25 @@ pub struct GSpanConfig {
     max_pat_max: usize,
     directed: bool,
     single_nodes: bool,
+    mode: MiningMode,
+    /// Incoming edges per vertex of the graphs, only for directed graphs outside of
+    /// [`MiningMode::All`].
+    incoming: Vec<Vec<Vec<Edge>>>,
+    /// String labels used by [`GSpanConfig::run`] for the output file.
+    pub(crate) labels: Option<LabelDictionary>,
+    /// Whether [`GSpanConfig::run`] writes the supporting graph ids of each pattern.
+    pub(crate) graph_ids: bool,
+    /// Maximum number of embeddings per graph collected for each pattern, if any.
+    pub(crate) embeddings: Option<usize>,
+    /// File to which [`GSpanConfig::run`] writes the embeddings.
+    pub(crate) embeddings_path: Option<String>,
+    /// Cancellation and limits of the mining runs.
+    pub(crate) budget: Budget,
+    pub(crate) progress: Option<ProgressHook>,
 }
 
 impl GSpanConfig {
+    /// Creates the configuration without validating it, prefer [`GSpanConfig::builder`].
     pub fn new(
         graphs: Vec<Graph>,
         min_sup: usize,
@@ -28,6 +55,11 @@ impl GSpanConfig {
         single_nodes: bool,
         out_path: String,
     ) -> GSpanConfig {
+        let mut graphs = graphs;
+        // The search relies on dense edge ids per graph, also for graphs built by hand
+        for graph in graphs.iter_mut() {
+            graph.build_edge();
+        }
         GSpanConfig {
             trans: graphs,
             min_sup,
@@ -35,13 +67,97 @@ impl GSpanConfig {
             max_pat_max,
             directed,
             single_nodes,
+            mode: MiningMode::All,
+            incoming: Vec::new(),
             out_path,
+            labels: None,
+            graph_ids: false,
+            embeddings: None,
+            embeddings_path: None,
+            budget: Budget::default(),
+            progress: None,
+        }
+    }
+
+    /// Returns a builder with named setters which validates the configuration.
+    pub fn builder(graphs: Vec<Graph>) -> GSpanConfigBuilder {
+        GSpanConfigBuilder::new(graphs)
+    }
+
+    /// Mines the frequent subgraphs and writes them to the output file.
+    /// Returns the number of reported subgraphs and whether the run stopped early.
+    pub fn run(&self) -> Result<RunSummary, Error> {
+        let mut out = TextSink::create(&self.out_path)
+            .map_err(Error::Output)?
+            .with_graph_ids(self.graph_ids);
+        if let Some(labels) = &self.labels {
+            out = out.with_labels(labels.clone());
+        }
+        match &self.embeddings_path {
+            Some(path) => {
+                let embeddings = EmbeddingSink::create(path).map_err(Error::Output)?;
+                self.run_with_sink(&mut (out, embeddings))
+            }
+            None => self.run_with_sink(&mut out),
+        }
+    }
+
+    /// Sets the mining mode and prepares the graphs for it.
+    pub(crate) fn set_mode(&mut self, mode: MiningMode) {
+        self.mode = mode;
+        if mode != MiningMode::All && self.directed {
+            self.incoming = incoming_edges(&self.trans);
+        }
+    }
+
+    /// The minimum support as number of graphs, relative supports given to the builder
+    /// are resolved against the number of graphs.
+    pub fn min_sup(&self) -> usize {
+        self.min_sup
+    }
+
+    /// The string labels of the graphs, if they were given to the builder.
+    pub fn labels(&self) -> Option<&LabelDictionary> {
+        self.labels.as_ref()
+    }
+
+    /// Mines the frequent subgraphs and returns them instead of writing the output file.
+    pub fn run_in_memory(&self) -> Vec<Pattern> {
+        self.patterns().collect()
+    }
+
+    /// Mines the frequent subgraphs and reports each of them to the given sink.
+    /// Returns the number of reported subgraphs and whether the run stopped early, or the
+    /// first error of the sink. The sink is finished in both cases, an error of
+    /// [`PatternSink::finish`] after an error of [`PatternSink::report`] is dropped.
+    pub fn run_with_sink(&self, out: &mut dyn PatternSink) -> Result<RunSummary, Error> {
+        let mut patterns = self.patterns();
+        let mut count = 0;
+        for pattern in patterns.by_ref() {
+            if let Err(err) = out.report(pattern) {
+                let _ = out.finish();
+                return Err(Error::Output(err));
+            }
+            count += 1;
         }
+        out.finish().map_err(Error::Output)?;
+        Ok(RunSummary {
+            patterns: count,
+            min_sup: self.min_sup,
+            stop_reason: patterns.stop_reason(),
+            stats: patterns.stats().clone(),
+        })
     }
 
-    pub fn run(&self) -> usize {
-        // 0. Define output file
-        let mut out: BufWriter<File> = BufWriter::new(File::create(self.out_path.clone()).unwrap());
+    /// Returns an iterator mining the frequent subgraphs on demand.
+    ///
+    /// Patterns are produced in the same order as by [`GSpanConfig::run`]. Mining only
+    /// continues when the next pattern is requested, so dropping the iterator stops it.
+    /// The iterator ends early if the budget of the configuration is used up, see
+    /// [`Patterns::stop_reason`].
+    pub fn patterns(&self) -> Patterns<'_> {
+        let started = Instant::now();
+        let deadline = self.budget.time_limit.map(|limit| started + limit);
         let mut next_id: usize = 0;
         // 1. Find single node frequent subgraph, if requested
         let mut single_vertex: BTreeMap<usize, BTreeMap<isize, usize>> = BTreeMap::new();
@@ -62,28 +178,43 @@ impl GSpanConfig {
             }
         }
 
-        //println!("{:?}", single_vertex);
-        //println!("{:?}", single_vertex_label);
         // 2. Report the single vertex subgraphs
+        let mut singles: Vec<Pattern> = Vec::new();
         for (frequent_label, value) in single_vertex_label.iter() {
             if value < &self.min_sup {
                 continue;
             }
+            let extension_support = match self.mode {
+                MiningMode::All => None,
+                MiningMode::Closed => Some(*value),
+                MiningMode::Maximal => Some(self.min_sup),
+            };
+            if extension_support
+                .is_some_and(|support| self.single_has_extension(*frequent_label, support))
+            {
+                continue;
+            }
 
-            let id = next_id.clone();
+            let id = next_id;
             next_id += 1;
             let mut g = Graph::new(id, self.directed);
             let v = g.create_vertex();
             v.label = *frequent_label;
 
-            let mut counts: Vec<usize> = Vec::with_capacity(64);
-            counts.resize(self.trans.len(), 0);
+            let mut graph_ids: Vec<usize> = Vec::new();
             for (key, it2) in single_vertex.iter() {
-                counts[*key] = *it2.get(&frequent_label).or(Some(&0)).unwrap();
+                if it2.contains_key(frequent_label) {
+                    graph_ids.push(self.trans[*key].original_id);
+                }
             }
 
-            // Report single-graphs
-            self.report_single(&mut out, &mut g, counts.iter().sum());
+            // Report single-graphs, the support is the number of graphs as for all patterns
+            if let Some(mut pattern) = self.report_single(g, *value, graph_ids) {
+                if let Some(limit) = self.embeddings {
+                    pattern.embeddings = self.single_embeddings(*frequent_label, limit);
+                }
+                singles.push(pattern);
+            }
         }
         // 3. Subgraphs > Verticies
         let mut root: BTreeMap<isize, BTreeMap<usize, BTreeMap<isize, Projected>>> =
@@ -91,89 +222,188 @@ impl GSpanConfig {
         let mut edges: Vec<&Edge> = Vec::with_capacity(32);
         for g in &self.trans {
             for from in &g.vertices {
-                if get_forward_root(&g, from, &mut edges) {
+                if get_forward_root(g, from, &mut edges) {
                     for edge in &edges {
                         let key_1 = from.label;
-                        let root_1 = root.entry(key_1).or_insert(BTreeMap::new());
+                        let root_1 = root.entry(key_1).or_default();
                         let key_2 = edge.e_label;
-                        let root_2 = root_1.entry(key_2).or_insert(BTreeMap::new());
+                        let root_2 = root_1.entry(key_2).or_default();
                         let key_3 = g.vertices.get(edge.to).unwrap().label;
-                        let root_3 = root_2.entry(key_3).or_insert(Projected::new());
+                        let root_3 = root_2.entry(key_3).or_default();
                         root_3.push(g.id, edge, None);
                     }
                 }
             }
         }
 
-        let mut dfs_code = DFSCode::new();
-        for (from_label_key, from_label) in root.iter() {
-            for (e_label_key, e_label) in from_label.iter() {
-                for (to_label_key, to_label) in e_label.iter() {
-                    dfs_code.push(0, 1, *from_label_key, *e_label_key, *to_label_key);
-                    self.project(to_label, &mut dfs_code, &mut next_id, &mut out);
-                    dfs_code.pop();
+        let mut children = Vec::new();
+        for (from_label_key, from_label) in root.into_iter() {
+            for (e_label_key, e_label) in from_label.into_iter() {
+                for (to_label_key, to_label) in e_label.into_iter() {
+                    children.push((
+                        DFS::from(0, 1, Some(from_label_key), e_label_key, Some(to_label_key)),
+                        to_label,
+                    ));
                 }
             }
         }
-        next_id
+        Patterns {
+            config: self,
+            singles: singles.into_iter(),
+            roots: children.len(),
+            stack: vec![Frame::new(children)],
+            dfs_code: DFSCode::new(),
+            next_id,
+            started,
+            deadline,
+            reported: 0,
+            stop_reason: None,
+            stats: Stats::default(),
+        }
     }
 
-    fn report_single(
-        &self,
-        out: &mut BufWriter<File>,
-        g: &mut Graph,
-        sup: usize,
-    ) {
+    fn report_single(&self, g: Graph, sup: usize, graph_ids: Vec<usize>) -> Option<Pattern> {
         if self.max_pat_max >= self.max_pat_min && g.vertices.len() > self.max_pat_max {
-            return;
+            return None;
         }
         if self.max_pat_min > 0 && g.vertices.len() < self.max_pat_min {
-            return;
+            return None;
         }
-        out.write(&*g.to_str_repr(Some(sup)).into_bytes()).unwrap();
-        out.write(b"\n").unwrap();
+        Some(Pattern::new(g, DFSCode::new(), sup, graph_ids))
     }
 
     fn report(
         &self,
         sup: usize,
+        projected: &Projected,
         dfs_code: &DFSCode,
         next_id: &mut usize,
-        out: &mut BufWriter<File>,
-    ) {
+    ) -> Option<Pattern> {
         if self.max_pat_max >= self.max_pat_min && dfs_code.count_node() > self.max_pat_max {
-            return;
+            return None;
         }
         if self.max_pat_min > 0 && dfs_code.count_node() < self.max_pat_min {
-            return;
+            return None;
         }
-        let id = next_id.clone();
+        let id = *next_id;
         *next_id += 1;
         let mut g = Graph::new(id, self.directed);
         dfs_code.to_graph(&mut g, self.single_nodes);
-        out.write(&*g.to_str_repr(Some(sup)).into_bytes()).unwrap();
-        out.write(b"\n").unwrap();
+        let mut pattern = Pattern::new(
+            g,
+            dfs_code.clone(),
+            sup,
+            projected
+                .graph_ids()
+                .into_iter()
+                .map(|id| self.trans[id].original_id)
+                .collect(),
+        );
+        if let Some(limit) = self.embeddings {
+            pattern.embeddings = self.embeddings(projected, dfs_code, limit);
+        }
+        Some(pattern)
     }
 
-    fn project(
-        &self,
-        projected: &Projected,
-        dfs_code: &mut DFSCode,
+    /// Vertex mappings of the first `limit` projections per graph, with the original
+    /// graph and vertex ids. The edges of a projection follow the edges of the DFS code.
+    fn embeddings(&self, projected: &Projected, dfs_code: &DFSCode, limit: usize) -> Vec<Match> {
+        if limit == 0 {
+            return Vec::new();
+        }
+        let mut matches: Vec<Match> = Vec::new();
+        let mut last_id = None;
+        for projection in &projected.projections {
+            let graph = &self.trans[projection.id];
+            if last_id != Some(projection.id) {
+                last_id = Some(projection.id);
+                matches.push(Match {
+                    graph_id: graph.original_id,
+                    mappings: Vec::new(),
+                });
+            }
+            let found = matches.last_mut().unwrap();
+            if found.mappings.len() >= limit {
+                continue;
+            }
+            let history = History::build(projection);
+            let mapping = vertex_mapping(dfs_code, &history)
+                .into_iter()
+                .map(|v| graph.vertices[v].original_id)
+                .collect();
+            found.mappings.push(mapping);
+        }
+        matches
+    }
+
+    /// The first `limit` vertices with the label per graph, as embeddings of a single
+    /// vertex pattern.
+    fn single_embeddings(&self, label: isize, limit: usize) -> Vec<Match> {
+        self.trans
+            .iter()
+            .filter_map(|graph| {
+                let mappings: Vec<Vec<usize>> = graph
+                    .vertices
+                    .iter()
+                    .filter(|v| v.label == label)
+                    .take(limit)
+                    .map(|v| vec![v.original_id])
+                    .collect();
+                if mappings.is_empty() {
+                    return None;
+                }
+                Some(Match {
+                    graph_id: graph.original_id,
+                    mappings,
+                })
+            })
+            .collect()
+    }
+
+    /// Processes the pattern described by `dfs_code` and its projections. Returns the
+    /// pattern, if it has to be reported, and the frame with all one edge extensions
+    /// which have to be visited next, if the pattern is frequent and minimal.
+    fn project<'a>(
+        &'a self,
+        projected: &Projected<'a>,
+        dfs_code: &DFSCode,
         next_id: &mut usize,
-        out: &mut BufWriter<File>,
-    ) {
+        stats: &mut Stats,
+    ) -> (Option<Pattern>, Option<Frame<'a>>) {
+        stats.extensions_tried += 1;
+        stats.peak_projections = stats.peak_projections.max(projected.projections.len());
         // Check if the pattern is frequent enough
         let sup: usize = self.support(projected);
         if sup < self.min_sup {
-            return;
+            return (None, None);
         }
+        stats.extensions_frequent += 1;
         // Check if the pattern is not min
-        if !self.is_min(dfs_code) {
-            return;
+        let started = Instant::now();
+        let is_min = self.is_min(dfs_code);
+        stats.min_check_time += started.elapsed();
+        stats.is_min_checks += 1;
+        if !is_min {
+            stats.is_min_failed += 1;
+            return (None, None);
         }
 
-        // Output the frequent substructures
-        self.report(sup, dfs_code, next_id, out);
+        // Output the frequent substructures, in closed mode only if no extension has the
+        // same support. Maximal patterns are reported once their extensions are known.
+        let extensions = match self.mode {
+            MiningMode::Closed => Some(self.extensions(projected, dfs_code)),
+            _ => None,
+        };
+        let closed = extensions.as_ref().is_none_or(|extensions| {
+            !self.has_extension(extensions, sup, dfs_code.count_node(), || {
+                self.pattern_graph(dfs_code)
+            })
+        });
+        let mut pattern = if closed && self.mode != MiningMode::Maximal {
+            self.report(sup, projected, dfs_code, next_id)
+        } else {
+            None
+        };
 
         /*
          * In case we have a valid upper bound and our graph already exceeds it,
@@ -182,17 +412,26 @@ impl GSpanConfig {
          * number of nodes.
          */
         if self.max_pat_max >= self.max_pat_min && dfs_code.count_node() > self.max_pat_max {
-            return;
+            return (pattern, None);
         }
 
         /*
          * We just outputted a frequent sub-graph. As it is frequent enough, so
          * might be its (n+1)-extension-graphs, hence we enumerate them all.
          */
+        let started = Instant::now();
 
         let rm_path = dfs_code.build_rm_path();
-        let min_label = dfs_code.dfs_vec.get(0).unwrap().from_label;
-        let max_toc = dfs_code.dfs_vec.get(*rm_path.get(0).unwrap()).unwrap().to;
+        let min_label = dfs_code.dfs_vec.first().unwrap().from_label.unwrap();
+        let max_toc = dfs_code.dfs_vec.get(*rm_path.first().unwrap()).unwrap().to;
+
+        if let Some(extensions) = &extensions {
+            if self.can_terminate(extensions, dfs_code, &rm_path) {
+                stats.early_terminations += 1;
+                stats.extension_time += started.elapsed();
+                return (pattern, None);
+            }
+        }
 
         let mut new_fwd_root: BTreeMap<usize, BTreeMap<usize, BTreeMap<isize, Projected>>> =
             BTreeMap::new();
@@ -205,33 +444,33 @@ impl GSpanConfig {
             let history = History::build(a_projected);
 
             // backward
-            for i in (0..rm_path.len()).rev() {
+            for i in (1..rm_path.len()).rev() {
                 let e = get_backward(
                     self.trans.get(id).unwrap(),
-                    history.histories.get(*rm_path.get(0).unwrap()).unwrap(),
                     history.histories.get(*rm_path.get(i).unwrap()).unwrap(),
+                    history.histories.get(*rm_path.first().unwrap()).unwrap(),
                     &history,
                 );
                 if let Some(e) = e {
                     let key_1 = dfs_code.dfs_vec.get(*rm_path.get(i).unwrap()).unwrap().from;
-                    let root_1 = new_bck_root.entry(key_1).or_insert(BTreeMap::new());
+                    let root_1 = new_bck_root.entry(key_1).or_default();
                     let key_2 = e.e_label;
-                    let root_2 = root_1.entry(key_2).or_insert(Projected::new());
-                    root_2.push(id, e, Some(&a_projected));
+                    let root_2 = root_1.entry(key_2).or_default();
+                    root_2.push(id, e, Some(a_projected));
                 }
             }
             // pure forward
             if get_forward_pure(
                 self.trans.get(id).unwrap(),
-                history.histories.get(*rm_path.get(0).unwrap()).unwrap(),
+                history.histories.get(*rm_path.first().unwrap()).unwrap(),
                 min_label,
                 &history,
                 &mut edges,
             ) {
                 for it in &edges {
-                    let root_1 = new_fwd_root.entry(max_toc).or_insert(BTreeMap::new());
+                    let root_1 = new_fwd_root.entry(max_toc).or_default();
                     let key_2 = it.e_label;
-                    let root_2 = root_1.entry(key_2).or_insert(BTreeMap::new());
+                    let root_2 = root_1.entry(key_2).or_default();
                     let key_3 = self
                         .trans
                         .get(id)
@@ -240,8 +479,8 @@ impl GSpanConfig {
                         .get(it.to)
                         .unwrap()
                         .label;
-                    let root_3 = root_2.entry(key_3).or_insert(Projected::new());
-                    root_3.push(id, it, Some(&a_projected));
+                    let root_3 = root_2.entry(key_3).or_default();
+                    root_3.push(id, it, Some(a_projected));
                 }
             }
             // backtracked forward
@@ -255,9 +494,9 @@ impl GSpanConfig {
                 ) {
                     for it in &edges {
                         let key_1 = dfs_code.dfs_vec.get(*a_rm_path).unwrap().from;
-                        let root_1 = new_fwd_root.entry(key_1).or_insert(BTreeMap::new());
+                        let root_1 = new_fwd_root.entry(key_1).or_default();
                         let key_2 = it.e_label;
-                        let root_2 = root_1.entry(key_2).or_insert(BTreeMap::new());
+                        let root_2 = root_1.entry(key_2).or_default();
                         let key_3 = self
                             .trans
                             .get(id)
@@ -266,31 +505,167 @@ impl GSpanConfig {
                             .get(it.to)
                             .unwrap()
                             .label;
-                        let root_3 = root_2.entry(key_3).or_insert(Projected::new());
+                        let root_3 = root_2.entry(key_3).or_default();
                         root_3.push(id, it, Some(a_projected));
                     }
                 }
             }
         }
         // Test all extended substructures..
+        let mut children = Vec::new();
         // .. backward
-        for (to_key, to) in new_bck_root.iter() {
-            for (e_label_key, e_label) in to.iter() {
-                dfs_code.push(max_toc, *to_key, -1, *e_label_key, -1);
-                self.project(e_label, dfs_code, next_id, out);
-                dfs_code.pop();
+        for (to_key, to) in new_bck_root.into_iter() {
+            for (e_label_key, e_label) in to.into_iter() {
+                children.push((DFS::from(max_toc, to_key, None, e_label_key, None), e_label));
             }
         }
         // .. forward
-        for (from_key, from) in new_fwd_root.iter().rev() {
-            for (e_label_key, e_label) in from.iter() {
-                for (to_label_key, to_label) in e_label.iter() {
-                    dfs_code.push(*from_key, max_toc + 1, -1, *e_label_key, *to_label_key);
-                    self.project(to_label, dfs_code, next_id, out);
-                    dfs_code.pop();
+        for (from_key, from) in new_fwd_root.into_iter().rev() {
+            for (e_label_key, e_label) in from.into_iter() {
+                for (to_label_key, to_label) in e_label.into_iter() {
+                    children.push((
+                        DFS::from(from_key, max_toc + 1, None, e_label_key, Some(to_label_key)),
+                        to_label,
+                    ));
                 }
             }
         }
+        stats.extension_time += started.elapsed();
+        if self.mode == MiningMode::Maximal && self.is_maximal(projected, dfs_code, &children) {
+            pattern = self.report(sup, projected, dfs_code, next_id);
+        }
+        (pattern, Some(Frame::new(children)))
+    }
+
+    /// Whether no extension of the pattern is frequent. For undirected graphs, the
+    /// rightmost extensions of the search are checked first. The other extensions are
+    /// only visited in other branches of the search, so all one edge extensions are
+    /// checked after them. A frequent rightmost extension of a directed pattern may not be
+    /// reported, see [`is_reachable`], so only the latter check is used.
+    fn is_maximal(
+        &self,
+        projected: &Projected,
+        dfs_code: &DFSCode,
+        children: &[(DFS, Projected)],
+    ) -> bool {
+        let vertices = dfs_code.count_node();
+        let capped = self.max_pat_max >= self.max_pat_min && vertices >= self.max_pat_max;
+        let frequent_child = |(dfs, projected): &(DFS, Projected)| {
+            (dfs.is_backward() || !capped) && self.support(projected) >= self.min_sup
+        };
+        if !self.directed && children.iter().any(frequent_child) {
+            return false;
+        }
+        let extensions = self.extensions(projected, dfs_code);
+        !self.has_extension(&extensions, self.min_sup, vertices, || {
+            self.pattern_graph(dfs_code)
+        })
+    }
+
+    /// All one edge extensions of the projections of the pattern.
+    fn extensions(&self, projected: &Projected, dfs_code: &DFSCode) -> Extensions {
+        let embeddings = projected.projections.iter().map(|projection| {
+            let history = History::build(projection);
+            Embedding {
+                graph: projection.id,
+                vertices: vertex_mapping(dfs_code, &history),
+                edges: history.edges,
+            }
+        });
+        Extensions::collect(&self.trans, &self.incoming, embeddings)
+    }
+
+    /// The unmerged pattern graph of the DFS code.
+    fn pattern_graph(&self, dfs_code: &DFSCode) -> Graph {
+        let mut g = Graph::new(0, self.directed);
+        dfs_code.to_graph(&mut g, false);
+        g
+    }
+
+    /// Whether the single vertex pattern has an extension in at least `support` graphs,
+    /// see [`GSpanConfig::has_extension`].
+    fn single_has_extension(&self, label: isize, support: usize) -> bool {
+        let embeddings = self.trans.iter().flat_map(|graph| {
+            graph
+                .vertices
+                .iter()
+                .filter(|v| v.label == label)
+                .map(|v| Embedding {
+                    graph: graph.id,
+                    vertices: vec![v.id],
+                    edges: Default::default(),
+                })
+        });
+        let extensions = Extensions::collect(&self.trans, &self.incoming, embeddings);
+        self.has_extension(&extensions, support, 1, || {
+            let mut g = Graph::new(0, self.directed);
+            g.add_vertex(label);
+            g
+        })
+    }
+
+    /// Whether an extension of the pattern with `vertices` vertices occurs in at least
+    /// `support` graphs and leads to a pattern which is reported in [`MiningMode::All`],
+    /// i.e. within the vertex limits and, for directed graphs, reachable by the search.
+    fn has_extension<F>(
+        &self,
+        extensions: &Extensions,
+        support: usize,
+        vertices: usize,
+        pattern: F,
+    ) -> bool
+    where
+        F: Fn() -> Graph,
+    {
+        let capped = self.max_pat_max >= self.max_pat_min && vertices >= self.max_pat_max;
+        extensions
+            .occurrences
+            .iter()
+            .any(|(extension, occurrences)| {
+                if occurrences.graphs < support
+                    || capped && matches!(extension, Extension::Outer { .. })
+                {
+                    return false;
+                }
+                if !self.directed {
+                    return true;
+                }
+                let mut g = pattern();
+                extension.apply(&mut g);
+                is_reachable(&g)
+            })
+    }
+
+    /// Early termination of closed mining: if every embedding of the pattern has an
+    /// edge between two pattern vertices which no extension in the search below the
+    /// pattern can add, adding that edge keeps the support of all these patterns, so
+    /// none of them is closed.
+    ///
+    /// Edges between existing vertices are only added as backward edges from the
+    /// rightmost vertex to the rightmost path, before the next forward edge. For
+    /// directed graphs, the supergraph with the edge may not be reachable by the search,
+    /// so the search is not terminated.
+    fn can_terminate(
+        &self,
+        extensions: &Extensions,
+        dfs_code: &DFSCode,
+        rm_path: &[usize],
+    ) -> bool {
+        if self.directed {
+            return false;
+        }
+        let rightmost = dfs_code.dfs_vec[rm_path[0]].to;
+        extensions
+            .occurrences
+            .iter()
+            .any(|(extension, occurrences)| match *extension {
+                Extension::Inner { from, to, .. } => {
+                    occurrences.embeddings == extensions.embeddings
+                        && !(to == rightmost
+                            && rm_path.iter().any(|&i| dfs_code.dfs_vec[i].from == from))
+                }
+                Extension::Outer { .. } => false,
+            })
     }
 
     fn support(&self, projected: &Projected) -> usize {
@@ -306,193 +681,610 @@ impl GSpanConfig {
         size
     }
 
-    fn is_min(&self, dfs_code: &mut DFSCode) -> bool {
+    fn is_min(&self, dfs_code: &DFSCode) -> bool {
         if dfs_code.dfs_vec.len() == 1 {
             return true;
         }
 
+        // The minimality check needs the pattern itself, merged vertices only affect the output
         let mut graph_is_min = Graph::new(0, self.directed);
-        dfs_code.to_graph(&mut graph_is_min, self.single_nodes);
+        dfs_code.to_graph(&mut graph_is_min, false);
+        min_dfs_code(&graph_is_min, Some(dfs_code)).is_some()
+    }
+}
 
-        let mut dfs_code_is_min = DFSCode::new();
+/// The graph vertex of every vertex of the DFS code in a projection.
+fn vertex_mapping(dfs_code: &DFSCode, history: &History) -> Vec<usize> {
+    let mut mapping = vec![0; dfs_code.count_node()];
+    for (dfs, edge) in dfs_code.dfs_vec.iter().zip(&history.histories) {
+        mapping[dfs.from] = edge.from;
+        mapping[dfs.to] = edge.to;
+    }
+    mapping
+}
 
-        let mut root: BTreeMap<isize, BTreeMap<usize, BTreeMap<isize, Projected>>> =
-            BTreeMap::new();
-        let mut edges: Vec<&Edge> = Vec::with_capacity(32);
+/// The one edge extensions of a pattern which still have to be visited.
+struct Frame<'a> {
+    /// Extensions in reverse visiting order, the next one is at the end.
+    children: Vec<(DFS, Projected<'a>)>,
+}
 
-        for from in &graph_is_min.vertices {
-            if get_forward_root(&graph_is_min, from, &mut edges) {
-                for it in &edges {
-                    let key_1 = from.label;
-                    let root_1 = root.entry(key_1).or_insert(BTreeMap::new());
-                    let key_2 = it.e_label;
-                    let root_2 = root_1.entry(key_2).or_insert(BTreeMap::new());
-                    let key_3 = graph_is_min.vertices.get(it.to).unwrap().label;
-                    let root_3 = root_2.entry(key_3).or_insert(Projected::new());
-                    root_3.push(graph_is_min.id, it, None);
-                }
+impl<'a> Frame<'a> {
+    fn new(mut children: Vec<(DFS, Projected<'a>)>) -> Frame<'a> {
+        children.reverse();
+        Frame { children }
+    }
+}
+
+/// Iterator over the frequent subgraphs, created by [`GSpanConfig::patterns`].
+///
+/// The depth first search of gSpan is driven by an explicit stack of frames instead of
+/// recursion, so each call to `next` only mines until the next pattern is found.
+pub struct Patterns<'a> {
+    config: &'a GSpanConfig,
+    singles: std::vec::IntoIter<Pattern>,
+    stack: Vec<Frame<'a>>,
+    dfs_code: DFSCode,
+    next_id: usize,
+    /// Number of frequent root edges.
+    roots: usize,
+    started: Instant,
+    deadline: Option<Instant>,
+    reported: usize,
+    stop_reason: Option<StopReason>,
+    stats: Stats,
+}
+
+impl Patterns<'_> {
+    /// Set once the iterator ended because the budget was used up, the patterns returned
+    /// so far are then only a part of all frequent subgraphs.
+    pub fn stop_reason(&self) -> Option<StopReason> {
+        self.stop_reason
+    }
+
+    /// Counters of the search so far.
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    /// Checks the cancellation token and the time limit, stops if either is used up.
+    fn out_of_budget(&mut self) -> bool {
+        if self.stop_reason.is_none() {
+            if let Some(reason) = self.config.budget.check(self.deadline) {
+                self.stop(reason);
             }
         }
+        self.stop_reason.is_some()
+    }
 
-        let from_label_binding = root.first_key_value().unwrap();
-        let from_label = from_label_binding.1;
-        let e_label_binding = from_label.first_key_value().unwrap();
-        let e_label = e_label_binding.1;
-        let to_label_binding = e_label.first_key_value().unwrap();
-        let to_label = to_label_binding.1;
-        dfs_code_is_min.push(
-            0,
-            1,
-            *from_label_binding.0,
-            *e_label_binding.0,
-            *to_label_binding.0,
-        );
-        self.is_min_project(to_label, dfs_code, &mut dfs_code_is_min, &graph_is_min)
+    /// Whether the maximum number of patterns was reported. Stops the search then, unless
+    /// nothing is left to search, so a run which ends with exactly the maximum number of
+    /// patterns is only partial if further extensions were not visited.
+    fn at_pattern_limit(&mut self) -> bool {
+        let Some(max_patterns) = self.config.budget.max_patterns else {
+            return false;
+        };
+        if self.reported < max_patterns {
+            return false;
+        }
+        let exhausted =
+            self.singles.len() == 0 && self.stack.iter().all(|frame| frame.children.is_empty());
+        if self.stop_reason.is_none() && !exhausted {
+            self.stop(StopReason::PatternLimit);
+        }
+        true
     }
 
-    fn is_min_project(
-        &self,
-        projected: &Projected,
-        dfs_code: &DFSCode,
-        dfs_code_is_min: &mut DFSCode,
-        graph_is_min: &Graph,
-    ) -> bool {
-        let rm_path = dfs_code_is_min.build_rm_path();
-        let min_label = dfs_code_is_min.dfs_vec.get(0).unwrap().from_label;
-        let max_toc: usize = dfs_code_is_min
-            .dfs_vec
-            .get(*rm_path.get(0).unwrap())
-            .unwrap()
-            .to;
+    /// Drops the remaining search space.
+    fn stop(&mut self, reason: StopReason) {
+        self.stop_reason = Some(reason);
+        self.singles = Vec::new().into_iter();
+        self.stack.clear();
+        self.dfs_code = DFSCode::new();
+    }
 
-        {
-            let mut root: BTreeMap<usize, Projected> = BTreeMap::new();
-            let mut new_to: usize = 0;
-            let mut flg = false;
-            for i in (1..rm_path.len()).rev() {
-                for cur in projected.projections.iter() {
-                    let cur = &**cur;
-                    let history: History = History::build(cur);
-                    let e = get_backward(
-                        graph_is_min,
-                        history.histories.get(*rm_path.get(i).unwrap()).unwrap(),
-                        history.histories.get(*rm_path.get(0).unwrap()).unwrap(),
-                        &history,
-                    );
-                    if let Some(e) = e {
-                        let key_1 = e.e_label;
-                        let root_1: &mut Projected = root.entry(key_1).or_insert(Projected::new());
-                        new_to = dfs_code_is_min
-                            .dfs_vec
-                            .get(*rm_path.get(i).unwrap())
-                            .unwrap()
-                            .from;
-                        root_1.push(key_1, e, Some(cur));
-                        flg = true;
-                    }
+    fn next_pattern(&mut self) -> Option<Pattern> {
+        if let Some(pattern) = self.singles.next() {
+            return Some(pattern);
+        }
+        while let Some(frame) = self.stack.last_mut() {
+            let Some((dfs, projected)) = frame.children.pop() else {
+                // All extensions visited, go back to the parent pattern
+                self.stack.pop();
+                if !self.stack.is_empty() {
+                    self.dfs_code.pop();
                 }
-                if flg {
-                    break;
+                continue;
+            };
+            self.dfs_code.dfs_vec.push(dfs);
+            let (pattern, frame) = self.config.project(
+                &projected,
+                &self.dfs_code,
+                &mut self.next_id,
+                &mut self.stats,
+            );
+            if let Some(hook) = &self.config.progress {
+                hook(&Progress {
+                    root: self.roots - self.stack[0].children.len(),
+                    roots: self.roots,
+                    depth: self.dfs_code.dfs_vec.len(),
+                    patterns: self.reported + pattern.is_some() as usize,
+                    elapsed: self.started.elapsed(),
+                });
+            }
+            match frame {
+                Some(frame) => self.stack.push(frame),
+                None => {
+                    self.dfs_code.pop();
                 }
             }
-            if flg {
-                let e_label = root.first_entry().unwrap();
-                dfs_code_is_min.push(max_toc, new_to, -1, *e_label.key(), -1);
-                if dfs_code
-                    .dfs_vec
-                    .get(dfs_code_is_min.dfs_vec.len() - 1)
+            if pattern.is_some() {
+                return pattern;
+            }
+            if self.out_of_budget() {
+                return None;
+            }
+        }
+        None
+    }
+}
+
+impl Iterator for Patterns<'_> {
+    type Item = Pattern;
+
+    fn next(&mut self) -> Option<Pattern> {
+        if self.out_of_budget() || self.at_pattern_limit() {
+            return None;
+        }
+        let pattern = self.next_pattern()?;
+        self.reported += 1;
+        self.stats.record_pattern(&pattern);
+        // Stop right away instead of searching on for a pattern which is not reported
+        self.at_pattern_limit();
+        Some(pattern)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::budget::CancellationToken;
+    use crate::models::graph::GraphBuilder;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    fn triangle(id: usize) -> Graph {
+        GraphBuilder::new(id, false)
+            .vertex(1)
+            .vertex(1)
+            .vertex(1)
+            .edge(0, 1, 1)
+            .edge(1, 2, 1)
+            .edge(0, 2, 1)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_run_in_memory() {
+        let graphs = vec![triangle(0), triangle(1)];
+        let gspan = GSpanConfig::new(graphs, 2, 1, 10, false, false, String::new());
+        let patterns = gspan.run_in_memory();
+        assert_eq!(patterns.len(), 4);
+        for pattern in &patterns {
+            assert_eq!(pattern.graph_ids, vec![0, 1]);
+        }
+        // Three vertices per graph, but two graphs
+        assert_eq!(patterns[0].support, 2);
+        let triangle = patterns.last().unwrap();
+        assert_eq!(triangle.graph.vertices.len(), 3);
+        assert_eq!(triangle.dfs_code.dfs_vec.len(), 3);
+        assert_eq!(triangle.support, 2);
+    }
+
+    #[test]
+    fn test_cyclic_patterns() {
+        // A square with a diagonal, all cycles are closed by backward edges
+        let square = |id| {
+            GraphBuilder::new(id, false)
+                .vertex(1)
+                .vertex(1)
+                .vertex(1)
+                .vertex(1)
+                .edge(0, 1, 1)
+                .edge(1, 2, 1)
+                .edge(2, 3, 1)
+                .edge(3, 0, 1)
+                .edge(0, 2, 1)
+                .build()
+                .unwrap()
+        };
+        let gspan = GSpanConfig::new(
+            vec![square(0), square(1)],
+            2,
+            1,
+            10,
+            false,
+            false,
+            String::new(),
+        );
+        let sizes: Vec<(usize, usize)> = gspan
+            .run_in_memory()
+            .iter()
+            .filter(|p| !p.dfs_code.dfs_vec.is_empty())
+            .map(|p| (p.dfs_code.count_node(), p.dfs_code.dfs_vec.len()))
+            .collect();
+        // Triangle, square, triangle with a pendant edge and the whole graph
+        for cyclic in [(3, 3), (4, 4), (4, 5)] {
+            assert!(sizes.contains(&cyclic));
+        }
+        assert_eq!(sizes.iter().filter(|(v, e)| e >= v).count(), 4);
+    }
+
+    #[test]
+    fn test_self_loops() {
+        for directed in [false, true] {
+            let graph = |id| {
+                GraphBuilder::new(id, directed)
+                    .vertex(1)
+                    .edge(0, 0, 5)
+                    .build()
                     .unwrap()
-                    .ne(dfs_code_is_min
-                        .dfs_vec
-                        .get(dfs_code_is_min.dfs_vec.len() - 1)
-                        .unwrap())
-                {
-                    return false;
+            };
+            let gspan = GSpanConfig::new(
+                vec![graph(0), graph(1)],
+                2,
+                1,
+                10,
+                directed,
+                false,
+                String::new(),
+            );
+            let patterns = gspan.run_in_memory();
+            assert_eq!(patterns.len(), 1);
+            assert!(patterns[0].dfs_code.dfs_vec.is_empty());
+            assert_eq!(patterns[0].support, 2);
+        }
+    }
+
+    #[test]
+    fn test_patterns_iterator() {
+        let graphs = vec![triangle(0), triangle(1)];
+        let gspan = GSpanConfig::new(graphs, 2, 1, 10, false, false, String::new());
+        let all: Vec<String> = gspan
+            .run_in_memory()
+            .iter()
+            .map(|p| p.to_str_repr())
+            .collect();
+        let first: Vec<String> = gspan.patterns().take(2).map(|p| p.to_str_repr()).collect();
+        assert_eq!(first, all[..2]);
+        assert_eq!(gspan.patterns().count(), all.len());
+    }
+
+    #[test]
+    fn test_budget() {
+        let run = |graphs: Vec<Graph>, max_patterns| {
+            let gspan = GSpanConfig::builder(graphs)
+                .max_patterns(max_patterns)
+                .build()
+                .unwrap();
+            let mut out: Vec<Pattern> = Vec::new();
+            let summary = gspan.run_with_sink(&mut out).unwrap();
+            assert_eq!(summary.patterns, out.len());
+            summary
+        };
+        let summary = run(vec![triangle(0), triangle(1)], 2);
+        assert_eq!(summary.patterns, 2);
+        assert_eq!(summary.stop_reason, Some(StopReason::PatternLimit));
+        // The search stops at the last pattern, before visiting the remaining extensions
+        // which hold no further pattern
+        let summary = run(vec![triangle(0), triangle(1)], 4);
+        assert_eq!(summary.patterns, 4);
+        assert!(summary.is_partial());
+        // Exactly all patterns with nothing left to search is not a partial result
+        let edge = |id| {
+            GraphBuilder::new(id, false)
+                .vertex(1)
+                .vertex(1)
+                .edge(0, 1, 1)
+                .build()
+                .unwrap()
+        };
+        let summary = run(vec![edge(0), edge(1)], 2);
+        assert_eq!(summary.patterns, 2);
+        assert!(!summary.is_partial());
+        assert_eq!(run(vec![edge(0), edge(1)], 0).patterns, 0);
+
+        let token = CancellationToken::new();
+        let gspan = GSpanConfig::builder(vec![triangle(0), triangle(1)])
+            .cancellation_token(token.clone())
+            .build()
+            .unwrap();
+        let mut patterns = gspan.patterns();
+        assert!(patterns.next().is_some());
+        token.cancel();
+        assert!(patterns.next().is_none());
+        assert_eq!(patterns.stop_reason(), Some(StopReason::Cancelled));
+
+        let gspan = GSpanConfig::builder(vec![triangle(0), triangle(1)])
+            .time_limit(Duration::ZERO)
+            .build()
+            .unwrap();
+        assert_eq!(gspan.patterns().count(), 0);
+    }
+
+    #[test]
+    fn test_sink_error() {
+        /// Fails on the second pattern.
+        #[derive(Default)]
+        struct FailingSink {
+            reported: usize,
+            finished: bool,
+        }
+
+        impl PatternSink for FailingSink {
+            fn report(&mut self, _pattern: Pattern) -> std::io::Result<()> {
+                self.reported += 1;
+                if self.reported > 1 {
+                    return Err(std::io::Error::other("disk full"));
                 }
-                return self.is_min_project(e_label.get(), dfs_code, dfs_code_is_min, graph_is_min);
+                Ok(())
+            }
+
+            fn finish(&mut self) -> std::io::Result<()> {
+                self.finished = true;
+                Ok(())
             }
         }
 
-        {
-            let mut flg = false;
-            let mut new_from = 0;
-            let mut root: BTreeMap<usize, BTreeMap<isize, Projected>> = BTreeMap::new();
-            let mut edges: Vec<&Edge> = Vec::with_capacity(32);
+        let gspan = GSpanConfig::builder(vec![triangle(0), triangle(1)])
+            .build()
+            .unwrap();
+        let mut sink = FailingSink::default();
+        let result = gspan.run_with_sink(&mut sink);
+        assert!(matches!(result, Err(Error::Output(_))));
+        assert_eq!(sink.reported, 2);
+        assert!(sink.finished);
+    }
 
-            for cur in projected.projections.iter() {
-                let history: History = History::build(cur);
-                if get_forward_pure(
-                    graph_is_min,
-                    history.histories.get(*rm_path.get(0).unwrap()).unwrap(),
-                    min_label,
-                    &history,
-                    &mut edges,
-                ) {
-                    flg = true;
-                    new_from = max_toc;
-                    for it in &edges {
-                        let key_1 = it.e_label;
-                        let root_1 = root.entry(key_1).or_insert(BTreeMap::new());
-                        let key_2 = graph_is_min.vertices.get(it.to).unwrap().label;
-                        let root_2 = root_1.entry(key_2).or_insert(Projected::new());
-                        root_2.push(0, it, Some(cur));
-                    }
-                }
+    #[test]
+    fn test_stats() {
+        let gspan = GSpanConfig::builder(vec![triangle(0), triangle(1)])
+            .build()
+            .unwrap();
+        let summary = gspan.run_with_sink(&mut Vec::new()).unwrap();
+        let stats = &summary.stats;
+        assert_eq!(stats.patterns(), 4);
+        let by_vertices: Vec<(usize, usize)> =
+            stats.patterns_by_vertices.clone().into_iter().collect();
+        assert_eq!(by_vertices, vec![(1, 1), (2, 1), (3, 2)]);
+        assert_eq!(stats.patterns_by_edges.len(), 4);
+        assert!(stats.extensions_tried >= stats.extensions_frequent);
+        assert_eq!(stats.is_min_checks, stats.extensions_frequent);
+        assert_eq!(stats.is_min_checks - stats.is_min_failed, 3);
+        assert_eq!(stats.peak_projections, 12);
+    }
+
+    #[test]
+    fn test_progress() {
+        let calls: Arc<Mutex<Vec<Progress>>> = Arc::default();
+        let recorded = calls.clone();
+        let gspan = GSpanConfig::builder(vec![triangle(0), triangle(1)])
+            .progress(move |progress| recorded.lock().unwrap().push(progress.clone()))
+            .build()
+            .unwrap();
+        assert_eq!(gspan.patterns().count(), 4);
+        let calls = calls.lock().unwrap();
+        assert!(!calls.is_empty());
+        let last = calls.last().unwrap();
+        assert_eq!((last.root, last.roots, last.patterns), (1, 1, 4));
+        assert_eq!(calls.iter().map(|p| p.depth).max(), Some(3));
+    }
+
+    #[test]
+    fn test_embeddings() {
+        let graphs = vec![triangle(0), triangle(1)];
+        let gspan = GSpanConfig::builder(graphs.clone())
+            .embeddings(usize::MAX)
+            .build()
+            .unwrap();
+        // The projections are all occurrences of the pattern, as found by the matcher
+        for pattern in gspan.run_in_memory() {
+            let mut embeddings = pattern.embeddings.clone();
+            let mut matches = crate::matcher::find_matches(&pattern.graph, &graphs, None);
+            for found in embeddings.iter_mut().chain(matches.iter_mut()) {
+                found.mappings.sort();
             }
-            if !flg {
-                for i in 0..rm_path.len() {
-                    for cur in projected.projections.iter() {
-                        let history: History = History::build(cur);
-                        if get_forward_rm_path(
-                            graph_is_min,
-                            history.histories.get(*rm_path.get(i).unwrap()).unwrap(),
-                            min_label,
-                            &history,
-                            &mut edges,
-                        ) {
-                            flg = true;
-                            new_from = dfs_code_is_min
-                                .dfs_vec
-                                .get(*rm_path.get(i).unwrap())
-                                .unwrap()
-                                .from;
-                            for it in &edges {
-                                let key_1 = it.e_label;
-                                let root_1 = root.entry(key_1).or_insert(BTreeMap::new());
-                                let key_2 = graph_is_min.vertices.get(it.to).unwrap().label;
-                                let root_2 = root_1.entry(key_2).or_insert(Projected::new());
-                                root_2.push(0, it, Some(cur));
-                            }
-                        }
-                    }
-                    if flg {
-                        break;
-                    }
-                }
+            assert_eq!(embeddings, matches);
+        }
+
+        let gspan = GSpanConfig::builder(graphs).embeddings(2).build().unwrap();
+        let patterns = gspan.run_in_memory();
+        assert_eq!(patterns[0].embeddings[1].graph_id, 1);
+        assert_eq!(patterns[0].embeddings[1].mappings, vec![vec![0], vec![1]]);
+        let triangle = patterns.last().unwrap();
+        assert_eq!(triangle.embeddings.len(), 2);
+        assert_eq!(triangle.embeddings[0].mappings.len(), 2);
+        assert!(GSpanConfig::builder(Vec::new())
+            .embeddings_path("embeddings.txt")
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_closed() {
+        let gspan = GSpanConfig::builder(vec![triangle(0), triangle(1)])
+            .mode(MiningMode::Closed)
+            .build()
+            .unwrap();
+        let patterns = gspan.run_in_memory();
+        assert_eq!(patterns.len(), 1);
+        assert_eq!(patterns[0].dfs_code.dfs_vec.len(), 3);
+        assert_eq!(patterns[0].graph.id, 0);
+
+        // The search never reaches 1 -> 2 <- 3, so 1 -> 2 and the vertex 3 are closed
+        let graph = |id| {
+            GraphBuilder::new(id, true)
+                .vertex(1)
+                .vertex(2)
+                .vertex(3)
+                .edge(0, 1, 0)
+                .edge(2, 1, 0)
+                .build()
+                .unwrap()
+        };
+        let gspan = GSpanConfig::builder(vec![graph(0), graph(1)])
+            .directed(true)
+            .mode(MiningMode::Closed)
+            .build()
+            .unwrap();
+        let patterns: Vec<String> = gspan
+            .run_in_memory()
+            .iter()
+            .map(|p| p.to_str_repr())
+            .collect();
+        assert_eq!(
+            patterns,
+            vec!["t # 0 * 2\nv 0 3", "t # 1 * 2\nv 0 1\nv 1 2\ne 0 1 0"]
+        );
+    }
+
+    #[test]
+    fn test_maximal() {
+        let edge = GraphBuilder::new(2, false)
+            .vertex(1)
+            .vertex(1)
+            .edge(0, 1, 1)
+            .build()
+            .unwrap();
+        let run = |mode| {
+            let gspan = GSpanConfig::builder(vec![triangle(0), triangle(1), edge.clone()])
+                .mode(mode)
+                .build()
+                .unwrap();
+            let patterns: Vec<(usize, usize)> = gspan
+                .run_in_memory()
+                .iter()
+                .map(|p| (p.dfs_code.dfs_vec.len(), p.support))
+                .collect();
+            patterns
+        };
+        // The edge occurs in more graphs than its supergraphs, but they are frequent
+        assert_eq!(run(MiningMode::Closed), vec![(1, 3), (3, 2)]);
+        assert_eq!(run(MiningMode::Maximal), vec![(3, 2)]);
+        // Supergraphs above the vertex limit do not count
+        let gspan = GSpanConfig::builder(vec![triangle(0), triangle(1)])
+            .max_vertices(2)
+            .mode(MiningMode::Maximal)
+            .build()
+            .unwrap();
+        let patterns = gspan.run_in_memory();
+        assert_eq!(patterns.len(), 1);
+        assert_eq!(patterns[0].graph.vertices.len(), 2);
+
+        // Supergraphs the search never reaches do not count either, see test_closed
+        let graph = |id| {
+            GraphBuilder::new(id, true)
+                .vertex(1)
+                .vertex(2)
+                .vertex(3)
+                .edge(0, 1, 0)
+                .edge(2, 1, 0)
+                .build()
+                .unwrap()
+        };
+        let gspan = GSpanConfig::builder(vec![graph(0), graph(1)])
+            .directed(true)
+            .mode(MiningMode::Maximal)
+            .build()
+            .unwrap();
+        let patterns: Vec<usize> = gspan
+            .run_in_memory()
+            .iter()
+            .map(|p| p.graph.vertices.len())
+            .collect();
+        assert_eq!(patterns, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_is_reachable() {
+        let graph = |labels: &[isize], edges: &[(usize, usize)]| {
+            let mut builder = GraphBuilder::new(0, true);
+            for &label in labels {
+                builder = builder.vertex(label);
             }
-            if flg {
-                let e_label_binding = root.first_key_value().unwrap();
-                let e_label = e_label_binding.1;
-                let to_label_binding = e_label.first_key_value().unwrap();
-                let to_label = to_label_binding.1;
-                dfs_code_is_min.push(
-                    new_from,
-                    max_toc + 1,
-                    -1,
-                    *e_label_binding.0,
-                    *to_label_binding.0,
-                );
-                if dfs_code
-                    .dfs_vec
-                    .get(dfs_code_is_min.dfs_vec.len() - 1)
-                    .unwrap()
-                    .ne(dfs_code_is_min.dfs_vec.last().unwrap())
-                {
-                    return false;
-                }
-                return self.is_min_project(to_label, dfs_code, dfs_code_is_min, graph_is_min);
+            for &(from, to) in edges {
+                builder = builder.edge(from, to, 0);
             }
+            builder.build().unwrap()
+        };
+        let cases = [
+            (graph(&[1, 1, 1], &[(0, 1), (1, 2)]), true),
+            // Directed cycle, closed by a backward edge
+            (graph(&[1, 1, 1], &[(0, 1), (1, 2), (2, 0)]), true),
+            // The edge to 2 is neither on the search tree nor backward
+            (graph(&[1, 1, 1], &[(0, 1), (0, 2), (2, 1)]), false),
+            (graph(&[1, 1, 1], &[(1, 0), (2, 0)]), false),
+            // Codes start at a vertex with the smallest label
+            (graph(&[2, 1], &[(0, 1)]), false),
+            (graph(&[1, 2, 1], &[(0, 1), (1, 2)]), true),
+        ];
+        for (g, reachable) in cases {
+            assert_eq!(is_reachable(&g), reachable);
+            // Mining the graph on its own reports a pattern with all of its edges
+            let edges = g.edge_size;
+            let config = GSpanConfig::new(vec![g], 1, 2, 3, true, false, String::new());
+            assert_eq!(
+                config
+                    .patterns()
+                    .any(|pattern| pattern.dfs_code.dfs_vec.len() == edges),
+                reachable
+            );
         }
-        true
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let graphs = vec![triangle(0), triangle(1)];
+        let json = serde_json::to_string(&graphs).unwrap();
+        let graphs: Vec<Graph> = serde_json::from_str(&json).unwrap();
+        let gspan = GSpanConfig::new(graphs, 2, 1, 10, false, false, String::new());
+        let patterns = gspan.run_in_memory();
+        let json = serde_json::to_string(&patterns).unwrap();
+        let copy: Vec<Pattern> = serde_json::from_str(&json).unwrap();
+        assert_eq!(copy.len(), patterns.len());
+        for (copy, pattern) in copy.iter().zip(&patterns) {
+            assert_eq!(copy.to_str_repr(), pattern.to_str_repr());
+            assert_eq!(copy.dfs_code.dfs_vec, pattern.dfs_code.dfs_vec);
+            assert_eq!(copy.graph_ids, pattern.graph_ids);
+        }
+    }
+
+    #[test]
+    fn test_negative_labels() {
+        let path = |id| {
+            GraphBuilder::new(id, false)
+                .vertex(-1)
+                .vertex(-2)
+                .vertex(-1)
+                .edge(0, 1, 1)
+                .edge(1, 2, 1)
+                .build()
+                .unwrap()
+        };
+        let gspan = GSpanConfig::new(
+            vec![path(0), path(1)],
+            2,
+            3,
+            10,
+            false,
+            false,
+            String::new(),
+        );
+        let patterns = gspan.run_in_memory();
+        assert_eq!(patterns.len(), 1);
+        let labels: Vec<isize> = patterns[0].graph.vertices.iter().map(|v| v.label).collect();
+        assert_eq!(labels, vec![-2, -1, -1]);
     }
 }
@@ -0,0 +1,48 @@
+//! Fast Rust implementation of the gSpan frequent subgraph mining algorithm.
+//!
+//! The crate can be used as a library or through the `gspan` command line tool.
+//! A typical run parses a graph database, configures the miner and writes all
+//! frequent subgraphs to an output file:
+//!
+//! ```no_run
+//! use gspan::{GSpanConfig, Graph};
+//!
+//! let graphs = Graph::graphs_set_from_file("graphs.txt", false).unwrap();
+//! let gspan = GSpanConfig::builder(graphs)
+//!     .min_sup(2)
+//!     .max_vertices(10)
+//!     .out_path("out.txt")
+//!     .build()
+//!     .unwrap();
+//! let summary = gspan.run().unwrap();
+//! println!("Found {} subgraphs", summary.patterns);
+//! ```
+
+pub mod budget;
+mod canonical;
+pub mod config;
+pub mod error;
+mod extensions;
+mod gspan;
+pub mod matcher;
+mod misc;
+pub mod models;
+pub mod parser;
+pub mod progress;
+pub mod sink;
+pub mod stats;
+
+pub use crate::budget::{CancellationToken, RunSummary, StopReason};
+pub use crate::config::{ConfigError, GSpanConfigBuilder, MiningMode, Support, SupportParseError};
+pub use crate::error::Error;
+pub use crate::gspan::{GSpanConfig, Patterns};
+pub use crate::matcher::{find_matches, Match};
+pub use crate::models::dfs::{DFSParseError, DFS};
+pub use crate::models::dfs_code::DFSCode;
+pub use crate::models::graph::{Graph, GraphBuilder, GraphError};
+pub use crate::models::labels::LabelDictionary;
+pub use crate::models::pattern::Pattern;
+pub use crate::parser::{GraphSetParseError, GraphSetParser, ParseMode};
+pub use crate::progress::Progress;
+pub use crate::sink::{PatternSink, TextSink};
+pub use crate::stats::Stats;
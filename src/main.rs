@@ -1,28 +1,35 @@
-use crate::gspan::GSpanConfig;
-use crate::models::graph::Graph;
-use std::time::Instant;
-
-mod gspan;
-mod misc;
-pub mod models;
-
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
+use gspan::{
+    find_matches, Error, GSpanConfig, GraphSetParser, LabelDictionary, MiningMode, ParseMode,
+    Progress, Stats, Support,
+};
+use std::fs;
+use std::fs::File;
+use std::io::{BufWriter, IsTerminal, Write};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 /// Fast Rust implementation for gSpan
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Input file with the graph database
-    #[arg(short, long)]
-    input: String,
+    #[arg(short, long, required = true)]
+    input: Option<String>,
 
     /// Output file for the resulting subgraphs
     #[arg(short, long, default_value = "out.txt")]
     output: String,
 
-    /// Min support
-    #[arg(short, long, default_value_t = 2)]
-    support: usize,
+    /// Min support, as number of graphs or relative to the input (e.g. 5% or 0.05)
+    #[arg(short, long, default_value = "2")]
+    support: Support,
 
     /// Minimum number of vertices
     #[arg(long, default_value_t = 1)]
@@ -35,37 +42,325 @@ struct Args {
     /// The graphs are directed
     #[arg(short, long, default_value_t = false)]
     directed: bool,
+
+    /// Report all errors of the input file instead of stopping at the first one
+    #[arg(long, default_value_t = false)]
+    all_errors: bool,
+
+    /// How strictly the input file is checked
+    #[arg(long, value_enum, default_value_t = Mode::Standard)]
+    parse_mode: Mode,
+
+    /// Vertex and edge labels are arbitrary strings instead of integers
+    #[arg(long, default_value_t = false)]
+    string_labels: bool,
+
+    /// Which frequent subgraphs are written
+    #[arg(long, value_enum, default_value_t = Mining::All)]
+    mode: Mining,
+
+    /// Merge vertices with the same label in the written patterns
+    #[arg(long, default_value_t = false)]
+    single_nodes: bool,
+
+    /// Write the ids of the graphs containing each subgraph as an `x:` line
+    #[arg(long, default_value_t = false)]
+    graph_ids: bool,
+
+    /// Write the embeddings (vertex mappings) of each subgraph to this file
+    #[arg(long)]
+    embeddings: Option<String>,
+
+    /// Maximum number of embeddings written per subgraph and graph
+    #[arg(long, default_value_t = 10)]
+    max_embeddings: usize,
+
+    /// Stop mining after this many seconds and keep the patterns found so far
+    #[arg(long)]
+    time_limit: Option<u64>,
+
+    /// Stop mining after this many patterns
+    #[arg(long)]
+    max_patterns: Option<usize>,
+
+    /// Do not print a status line while mining
+    #[arg(short, long, default_value_t = false)]
+    quiet: bool,
+
+    /// Write the mining statistics as JSON to this file
+    #[arg(long)]
+    stats_json: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Find the graphs of the database which contain the given patterns
+    Match(MatchArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct MatchArgs {
+    /// Input file with the graph database
+    #[arg(short, long)]
+    input: String,
+
+    /// File with the patterns, in the same format as the graph database
+    #[arg(short, long)]
+    pattern: String,
+
+    /// Output file for the matches [default: stdout]
+    #[arg(short, long)]
+    output: Option<String>,
+
+    /// The graphs are directed
+    #[arg(short, long, default_value_t = false)]
+    directed: bool,
+
+    /// How strictly the input files are checked
+    #[arg(long, value_enum, default_value_t = Mode::Standard)]
+    parse_mode: Mode,
+
+    /// Vertex and edge labels are arbitrary strings instead of integers
+    #[arg(long, default_value_t = false)]
+    string_labels: bool,
+
+    /// Maximum number of vertex mappings written per graph
+    #[arg(long, default_value_t = 10)]
+    max_mappings: usize,
 }
 
-fn main() {
-    let args = Args::parse();
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Mode {
+    /// Single spaces, sequential ids, unknown line types are ignored
+    Standard,
+    /// Like standard, but unknown line types are rejected
+    Strict,
+    /// Any whitespace, '#' comments, sparse graph and vertex ids
+    Lenient,
+}
 
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Mining {
+    /// All frequent subgraphs
+    All,
+    /// Only subgraphs without a supergraph of the same support
+    Closed,
+    /// Only subgraphs without a frequent supergraph
+    Maximal,
+}
+
+impl From<Mining> for MiningMode {
+    fn from(mining: Mining) -> MiningMode {
+        match mining {
+            Mining::All => MiningMode::All,
+            Mining::Closed => MiningMode::Closed,
+            Mining::Maximal => MiningMode::Maximal,
+        }
+    }
+}
+
+impl From<Mode> for ParseMode {
+    fn from(mode: Mode) -> ParseMode {
+        match mode {
+            Mode::Standard => ParseMode::Standard,
+            Mode::Strict => ParseMode::Strict,
+            Mode::Lenient => ParseMode::Lenient,
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let mut args = Args::parse();
+    let result = match args.command.take() {
+        Some(Command::Match(match_args)) => run_match(match_args),
+        None => run(args),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::from(exit_code(&err))
+        }
+    }
+}
+
+/// Exit codes of the CLI, `2` is used by clap for invalid arguments.
+fn exit_code(err: &Error) -> u8 {
+    match err {
+        Error::Io(_) => 3,
+        Error::Parse(_) | Error::Graph(_) => 4,
+        Error::Config(_) => 5,
+        Error::Output(_) => 6,
+    }
+}
+
+fn run(args: Args) -> Result<(), Error> {
     println!("gSpan Subgraph Mining");
     println!("---------------------");
     println!("Using arguments:");
     println!("{:?}", args);
     let now = Instant::now();
-    let graphs = Graph::graphs_set_from_file(args.input, args.directed);
-    match graphs {
-        Ok(ref graphs) => {
-            println!("All good parsing input file, found {} graphs", graphs.len());
-        }
-        Err(err) => panic!("{}", err.to_string()),
-    }
-    let graphs = graphs.unwrap();
+    let parser = GraphSetParser::new(args.directed)
+        .collect_errors(args.all_errors)
+        .mode(args.parse_mode.into());
+    let mut labels = LabelDictionary::new();
+    let input = args.input.as_deref().unwrap_or_default();
+    let graphs = if args.string_labels {
+        parser.parse_file_with_labels(input, &mut labels)?
+    } else {
+        parser.parse_file(input)?
+    };
+    println!("All good parsing input file, found {} graphs", graphs.len());
+    let graph_count = graphs.len();
     println!("Mining subgraphs..");
-    let gspan = GSpanConfig::new(
-        graphs,
-        args.support,
-        args.min_vertices,
-        args.max_vertices,
-        args.directed,
-        false,
-        args.output,
-    );
-    let subgraphs = gspan.run();
+    let mut builder = GSpanConfig::builder(graphs)
+        .support(args.support)
+        .min_vertices(args.min_vertices)
+        .max_vertices(args.max_vertices)
+        .directed(args.directed)
+        .mode(args.mode.into())
+        .single_nodes(args.single_nodes)
+        .graph_ids(args.graph_ids)
+        .out_path(args.output);
+    if args.string_labels {
+        builder = builder.labels(labels);
+    }
+    if let Some(path) = args.embeddings {
+        builder = builder
+            .embeddings(args.max_embeddings)
+            .embeddings_path(path);
+    }
+    if let Some(seconds) = args.time_limit {
+        builder = builder.time_limit(Duration::from_secs(seconds));
+    }
+    if let Some(max_patterns) = args.max_patterns {
+        builder = builder.max_patterns(max_patterns);
+    }
+    let status_shown = Arc::new(AtomicBool::new(false));
+    if !args.quiet {
+        let status_shown = status_shown.clone();
+        let last_second = AtomicU64::new(0);
+        builder = builder.progress(move |progress| {
+            let second = progress.elapsed.as_secs();
+            if second > last_second.load(Ordering::Relaxed) {
+                last_second.store(second, Ordering::Relaxed);
+                status_shown.store(true, Ordering::Relaxed);
+                print_status(progress);
+            }
+        });
+    }
+    let gspan = builder.build()?;
+    let summary = gspan.run()?;
+    if status_shown.load(Ordering::Relaxed) && std::io::stderr().is_terminal() {
+        eprintln!();
+    }
     let delta = now.elapsed().as_millis();
-    println!("Finished.");
-    println!("Found {} subgraphs", subgraphs);
+    match summary.stop_reason {
+        Some(reason) => println!("Stopped early ({}), the result is partial.", reason),
+        None => println!("Finished."),
+    }
+    println!("Found {} subgraphs", summary.patterns);
+    println!("Min support: {} of {} graphs", summary.min_sup, graph_count);
     println!("Took {}ms", delta);
+    print_stats(&summary.stats);
+    if let Some(path) = args.stats_json {
+        fs::write(path, summary.stats.to_json() + "\n").map_err(Error::Output)?;
+    }
+    Ok(())
+}
+
+/// Writes one `t # <pattern id> * <number of graphs>` line per pattern, followed by one
+/// `m <graph id> <vertex ids>` line per mapping, listing the graph vertex of each pattern
+/// vertex.
+fn run_match(args: MatchArgs) -> Result<(), Error> {
+    let parser = GraphSetParser::new(args.directed).mode(args.parse_mode.into());
+    let mut labels = LabelDictionary::new();
+    let (graphs, patterns) = if args.string_labels {
+        (
+            parser.parse_file_with_labels(&args.input, &mut labels)?,
+            parser.parse_file_with_labels(&args.pattern, &mut labels)?,
+        )
+    } else {
+        (
+            parser.parse_file(&args.input)?,
+            parser.parse_file(&args.pattern)?,
+        )
+    };
+    let mut out: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(BufWriter::new(File::create(path).map_err(Error::Output)?)),
+        None => Box::new(std::io::stdout().lock()),
+    };
+    for pattern in &patterns {
+        let matches = find_matches(pattern, &graphs, Some(args.max_mappings));
+        writeln!(out, "t # {} * {}", pattern.original_id, matches.len()).map_err(Error::Output)?;
+        for found in &matches {
+            for mapping in &found.mappings {
+                let vertices: Vec<String> = mapping.iter().map(|v| v.to_string()).collect();
+                writeln!(out, "m {} {}", found.graph_id, vertices.join(" "))
+                    .map_err(Error::Output)?;
+            }
+        }
+    }
+    out.flush().map_err(Error::Output)
+}
+
+/// Prints the status line, overwriting the previous one on a terminal.
+fn print_status(progress: &Progress) {
+    let line = format!(
+        "Root edge {}/{}, depth {}, {} subgraphs found, {}s elapsed",
+        progress.root,
+        progress.roots,
+        progress.depth,
+        progress.patterns,
+        progress.elapsed.as_secs()
+    );
+    let mut stderr = std::io::stderr();
+    if stderr.is_terminal() {
+        let _ = write!(stderr, "\r{:<80}", line);
+        let _ = stderr.flush();
+    } else {
+        let _ = writeln!(stderr, "{}", line);
+    }
+}
+
+fn print_stats(stats: &Stats) {
+    println!("Statistics:");
+    for (vertices, count) in &stats.patterns_by_vertices {
+        println!("  Subgraphs with {} vertices: {}", vertices, count);
+    }
+    for (edges, count) in &stats.patterns_by_edges {
+        println!("  Subgraphs with {} edges: {}", edges, count);
+    }
+    println!(
+        "  Extensions: {} tried, {} frequent",
+        stats.extensions_tried, stats.extensions_frequent
+    );
+    println!(
+        "  Minimality checks: {} ({} not minimal)",
+        stats.is_min_checks, stats.is_min_failed
+    );
+    println!("  Peak projections: {}", stats.peak_projections);
+    if stats.early_terminations > 0 {
+        println!("  Early terminations: {}", stats.early_terminations);
+    }
+    println!(
+        "  Time in minimality checks: {}ms, extension enumeration: {}ms",
+        stats.min_check_time.as_millis(),
+        stats.extension_time.as_millis()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gspan::ConfigError;
+    use std::io;
+
+    #[test]
+    fn test_exit_code() {
+        assert_eq!(exit_code(&Error::Io(io::Error::other("missing"))), 3);
+        assert_eq!(exit_code(&Error::Parse(Vec::new())), 4);
+        assert_eq!(exit_code(&Error::Config(ConfigError::ZeroSupport)), 5);
+        assert_eq!(exit_code(&Error::Output(io::Error::other("full"))), 6);
+    }
 }
@@ -0,0 +1,356 @@
+use crate::models::graph::Graph;
+use rustc_hash::FxHashMap;
+
+/// The occurrences of a pattern in one graph of a database, see [`find_matches`] and
+/// [`crate::Pattern::embeddings`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Match {
+    /// Id of the graph in the input file (`Graph::original_id`).
+    pub graph_id: usize,
+    /// One entry per occurrence, mapping each pattern vertex (by index) to the id of a
+    /// graph vertex in the input file (`Vertex::original_id`).
+    pub mappings: Vec<Vec<usize>>,
+}
+
+/// Finds the graphs of the database which contain the pattern as a subgraph, with up to
+/// `limit` vertex mappings per graph (all if `None`).
+///
+/// Like the support of mined patterns, the pattern does not need to be an induced
+/// subgraph: every pattern edge has to exist with the same label (and direction) between
+/// the mapped vertices, further graph edges are allowed. Graphs which are not directed
+/// like the pattern never match.
+pub fn find_matches(pattern: &Graph, graphs: &[Graph], limit: Option<usize>) -> Vec<Match> {
+    graphs
+        .iter()
+        .filter_map(|graph| {
+            let mappings = find_mappings(pattern, graph, limit);
+            if mappings.is_empty() {
+                return None;
+            }
+            Some(Match {
+                graph_id: graph.original_id,
+                mappings: mappings
+                    .into_iter()
+                    .map(|mapping| {
+                        mapping
+                            .into_iter()
+                            .map(|v| graph.vertices[v].original_id)
+                            .collect()
+                    })
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+/// Returns up to `limit` mappings (all if `None`) of the pattern vertices to the vertices
+/// of `graph`, each as the `Vertex::id` of the graph vertex per pattern vertex. There are
+/// none if only one of the pattern and the graph is directed.
+pub fn find_mappings(pattern: &Graph, graph: &Graph, limit: Option<usize>) -> Vec<Vec<usize>> {
+    let mut state = MatchState::new(pattern, graph, limit);
+    if pattern.directed == graph.directed
+        && !pattern.vertices.is_empty()
+        && pattern.vertices.len() <= graph.vertices.len()
+        && limit != Some(0)
+    {
+        state.extend(0);
+    }
+    state.mappings
+}
+
+/// Edge of the pattern between the vertex at some position of the matching order and a
+/// vertex at an earlier position.
+struct PatternEdge {
+    /// The earlier vertex.
+    other: usize,
+    e_label: usize,
+    /// Whether the edge goes from the later to the earlier vertex.
+    outgoing: bool,
+}
+
+/// Backtracking search which maps the pattern vertices one by one, in an order in which
+/// every vertex (but the first of each component) is adjacent to an earlier one.
+struct MatchState<'a> {
+    pattern: &'a Graph,
+    graph: &'a Graph,
+    limit: Option<usize>,
+    order: Vec<usize>,
+    /// Edges to earlier vertices, per position of the matching order.
+    edges: Vec<Vec<PatternEdge>>,
+    /// Labels of the self loops, per position of the matching order.
+    loops: Vec<Vec<usize>>,
+    /// Graph vertices by label.
+    by_label: FxHashMap<isize, Vec<usize>>,
+    /// Graph vertex per pattern vertex.
+    mapping: Vec<usize>,
+    used: Vec<bool>,
+    mappings: Vec<Vec<usize>>,
+}
+
+impl<'a> MatchState<'a> {
+    fn new(pattern: &'a Graph, graph: &'a Graph, limit: Option<usize>) -> MatchState<'a> {
+        let n = pattern.vertices.len();
+        // Breadth first order over the edges in both directions
+        let mut neighbors: Vec<Vec<usize>> = vec![Vec::new(); n];
+        for vertex in &pattern.vertices {
+            for edge in &vertex.edges {
+                neighbors[edge.from].push(edge.to);
+                neighbors[edge.to].push(edge.from);
+            }
+        }
+        let mut order: Vec<usize> = Vec::with_capacity(n);
+        let mut position: Vec<Option<usize>> = vec![None; n];
+        for start in 0..n {
+            if position[start].is_some() {
+                continue;
+            }
+            position[start] = Some(order.len());
+            order.push(start);
+            let mut next = order.len() - 1;
+            while next < order.len() {
+                for &to in &neighbors[order[next]] {
+                    if position[to].is_none() {
+                        position[to] = Some(order.len());
+                        order.push(to);
+                    }
+                }
+                next += 1;
+            }
+        }
+        let mut edges: Vec<Vec<PatternEdge>> = (0..n).map(|_| Vec::new()).collect();
+        let mut loops: Vec<Vec<usize>> = vec![Vec::new(); n];
+        for vertex in &pattern.vertices {
+            for edge in &vertex.edges {
+                let (from, to) = (position[edge.from].unwrap(), position[edge.to].unwrap());
+                if from == to {
+                    loops[from].push(edge.e_label);
+                } else if from > to {
+                    edges[from].push(PatternEdge {
+                        other: edge.to,
+                        e_label: edge.e_label,
+                        outgoing: true,
+                    });
+                } else if to > from && pattern.directed {
+                    edges[to].push(PatternEdge {
+                        other: edge.from,
+                        e_label: edge.e_label,
+                        outgoing: false,
+                    });
+                }
+            }
+        }
+        let mut by_label: FxHashMap<isize, Vec<usize>> = FxHashMap::default();
+        for vertex in &graph.vertices {
+            by_label.entry(vertex.label).or_default().push(vertex.id);
+        }
+        MatchState {
+            pattern,
+            graph,
+            limit,
+            order,
+            edges,
+            loops,
+            by_label,
+            mapping: vec![usize::MAX; n],
+            used: vec![false; graph.vertices.len()],
+            mappings: Vec::new(),
+        }
+    }
+
+    fn done(&self) -> bool {
+        self.limit.is_some_and(|limit| self.mappings.len() >= limit)
+    }
+
+    /// Maps the pattern vertex at `position` of the matching order and all later ones.
+    fn extend(&mut self, position: usize) {
+        if position == self.order.len() {
+            self.mappings.push(self.mapping.clone());
+            return;
+        }
+        let v = self.order[position];
+        let label = self.pattern.vertices[v].label;
+        // Candidates are the neighbors of the image of an earlier neighbor, if possible
+        let anchor = self.edges[position]
+            .iter()
+            .find(|edge| !self.pattern.directed || !edge.outgoing);
+        let candidates: Vec<usize> = match anchor {
+            Some(edge) => {
+                let mut candidates: Vec<usize> = self.graph.vertices[self.mapping[edge.other]]
+                    .edges
+                    .iter()
+                    .map(|e| e.to)
+                    .collect();
+                // Parallel edges would yield the same mapping twice
+                candidates.sort_unstable();
+                candidates.dedup();
+                candidates
+            }
+            None => self.by_label.get(&label).cloned().unwrap_or_default(),
+        };
+        for candidate in candidates {
+            if self.used[candidate]
+                || self.graph.vertices[candidate].label != label
+                || !self.loops_match(position, candidate)
+                || !self.edges_match(position, candidate)
+            {
+                continue;
+            }
+            self.mapping[v] = candidate;
+            self.used[candidate] = true;
+            self.extend(position + 1);
+            self.used[candidate] = false;
+            self.mapping[v] = usize::MAX;
+            if self.done() {
+                return;
+            }
+        }
+    }
+
+    /// Whether the graph has a self loop at `candidate` for every self loop of the vertex
+    /// at `position`.
+    fn loops_match(&self, position: usize, candidate: usize) -> bool {
+        self.loops[position].iter().all(|&e_label| {
+            self.graph.vertices[candidate]
+                .edges
+                .iter()
+                .any(|e| e.to == candidate && e.e_label == e_label)
+        })
+    }
+
+    /// Whether all pattern edges between the vertex at `position` and earlier vertices
+    /// exist in the graph if the vertex is mapped to `candidate`.
+    fn edges_match(&self, position: usize, candidate: usize) -> bool {
+        self.edges[position].iter().all(|edge| {
+            let other = self.mapping[edge.other];
+            let (from, to) = if edge.outgoing {
+                (candidate, other)
+            } else {
+                (other, candidate)
+            };
+            self.graph.vertices[from]
+                .edges
+                .iter()
+                .any(|e| e.to == to && e.e_label == edge.e_label)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::graph::GraphBuilder;
+
+    fn path(id: usize, labels: &[isize], directed: bool) -> Graph {
+        let mut builder = GraphBuilder::new(id, directed);
+        for label in labels {
+            builder = builder.vertex(*label);
+        }
+        for i in 1..labels.len() {
+            builder = builder.edge(i - 1, i, 0);
+        }
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn test_find_mappings() {
+        let pattern = path(0, &[1, 2], false);
+        let graph = path(0, &[2, 1, 2], false);
+        assert_eq!(
+            find_mappings(&pattern, &graph, None),
+            vec![vec![1, 0], vec![1, 2]]
+        );
+        assert_eq!(find_mappings(&pattern, &graph, Some(1)).len(), 1);
+
+        // Not induced: a path matches a triangle
+        let triangle = GraphBuilder::new(0, false)
+            .vertex(1)
+            .vertex(1)
+            .vertex(1)
+            .edge(0, 1, 0)
+            .edge(1, 2, 0)
+            .edge(2, 0, 0)
+            .build()
+            .unwrap();
+        assert_eq!(
+            find_mappings(&path(0, &[1, 1, 1], false), &triangle, None).len(),
+            6
+        );
+        assert!(find_mappings(&triangle, &path(0, &[1, 1, 1], false), None).is_empty());
+    }
+
+    #[test]
+    fn test_find_mappings_directed() {
+        let pattern = path(0, &[1, 2], true);
+        assert_eq!(
+            find_mappings(&pattern, &path(0, &[1, 2], true), None),
+            vec![vec![0, 1]]
+        );
+        assert!(find_mappings(&pattern, &path(0, &[2, 1], true), None).is_empty());
+        // Undirected graphs have the edge in both directions, but never match
+        assert!(find_mappings(&pattern, &path(0, &[1, 2], false), None).is_empty());
+        assert!(find_mappings(&path(0, &[1, 2], false), &path(0, &[1, 2], true), None).is_empty());
+        // The first pattern vertex only has an incoming edge
+        let pattern = GraphBuilder::new(0, true)
+            .vertex(2)
+            .vertex(1)
+            .edge(1, 0, 0)
+            .build()
+            .unwrap();
+        assert_eq!(
+            find_mappings(&pattern, &path(0, &[1, 2, 1], true), None),
+            vec![vec![1, 0]]
+        );
+    }
+
+    #[test]
+    fn test_find_mappings_self_loops() {
+        for directed in [false, true] {
+            let pattern = GraphBuilder::new(0, directed)
+                .vertex(1)
+                .vertex(2)
+                .edge(0, 1, 0)
+                .edge(1, 1, 3)
+                .build()
+                .unwrap();
+            let graph = path(0, &[1, 2, 1], directed);
+            assert!(find_mappings(&pattern, &graph, None).is_empty());
+            // Only the second vertex labelled 2 has a self loop with the same label
+            let graph = |loops: &[(usize, usize)]| {
+                let mut builder = GraphBuilder::new(0, directed)
+                    .vertex(1)
+                    .vertex(2)
+                    .vertex(2)
+                    .edge(0, 1, 0)
+                    .edge(0, 2, 0);
+                for &(v, e_label) in loops {
+                    builder = builder.edge(v, v, e_label);
+                }
+                builder.build().unwrap()
+            };
+            assert_eq!(
+                find_mappings(&pattern, &graph(&[(1, 4), (2, 3)]), None),
+                vec![vec![0, 2]]
+            );
+            assert_eq!(
+                find_mappings(&pattern, &graph(&[(1, 3), (2, 3)]), None).len(),
+                2
+            );
+        }
+    }
+
+    #[test]
+    fn test_find_matches() {
+        let mut graphs = vec![path(0, &[1, 2], false), path(1, &[1, 3], false)];
+        graphs[1].original_id = 7;
+        graphs[1].vertices[1].original_id = 4;
+        let matches = find_matches(&path(0, &[3], false), &graphs, None);
+        assert_eq!(
+            matches,
+            vec![Match {
+                graph_id: 7,
+                mappings: vec![vec![4]]
+            }]
+        );
+    }
+}
@@ -6,18 +6,19 @@ use crate::models::vertex::Vertex;
 pub fn get_forward_root<'a>(g: &Graph, v: &'a Vertex, result: &mut Vec<&'a Edge>) -> bool {
     result.clear();
     for edge in &v.edges {
-        if v.label <= g.vertices.get(edge.to).unwrap().label {
+        // Self loops are not mined
+        if edge.from != edge.to && v.label <= g.vertices.get(edge.to).unwrap().label {
             result.push(edge);
         }
     }
     !result.is_empty()
 }
 
-pub fn get_backward<'a, 'b>(
+pub fn get_backward<'a>(
     g: &'a Graph,
     e1: &'a Edge,
     e2: &'a Edge,
-    history: &'b History,
+    history: &History,
 ) -> Option<&'a Edge> {
     if e1 == e2 {
         return None;
@@ -27,22 +28,22 @@ pub fn get_backward<'a, 'b>(
             continue;
         }
         if (edge.to == e1.from)
-            && ((e1.e_label == edge.e_label)
-            || (e1.e_label == edge.e_label)
-            && (g.vertices.get(e1.to).unwrap().label
-            <= g.vertices.get(e2.to).unwrap().label))
+            && ((e1.e_label < edge.e_label)
+                || (e1.e_label == edge.e_label)
+                    && (g.vertices.get(e1.to).unwrap().label
+                        <= g.vertices.get(e2.to).unwrap().label))
         {
-            return Some(&edge);
+            return Some(edge);
         }
     }
-    return None;
+    None
 }
 
-pub fn get_forward_pure<'a, 'b>(
+pub fn get_forward_pure<'a>(
     g: &'a Graph,
     e: &'a Edge,
     min_label: isize,
-    history: &'b History,
+    history: &History,
     result: &mut Vec<&'a Edge>,
 ) -> bool {
     result.clear();
@@ -50,16 +51,16 @@ pub fn get_forward_pure<'a, 'b>(
         if min_label > g.vertices.get(edge.to).unwrap().label || history.has_vertex(&edge.to) {
             continue;
         }
-        result.push(&edge);
+        result.push(edge);
     }
     !result.is_empty()
 }
 
-pub fn get_forward_rm_path<'a, 'b>(
+pub fn get_forward_rm_path<'a>(
     g: &'a Graph,
     e: &'a Edge,
     min_label: isize,
-    history: &'b History,
+    history: &History,
     result: &mut Vec<&'a Edge>,
 ) -> bool {
     result.clear();
@@ -70,7 +71,7 @@ pub fn get_forward_rm_path<'a, 'b>(
             continue;
         }
         if e.e_label < edge.e_label || (e.e_label == edge.e_label && to_label <= to_label_2) {
-            result.push(&edge);
+            result.push(edge);
         }
     }
     !result.is_empty()
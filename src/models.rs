@@ -3,6 +3,8 @@ pub mod dfs_code;
 pub mod edge;
 pub mod graph;
 pub mod history;
+pub mod labels;
+pub mod pattern;
 pub mod pdfs;
 pub mod projected;
 pub mod vertex;
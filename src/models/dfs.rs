@@ -1,10 +1,27 @@
-#[derive(PartialEq, Debug)]
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// One edge of a DFS code. The vertex labels are `None` if the vertex already occurs
+/// earlier in the code, i.e. for the from vertex of every edge but the first one and the
+/// to vertex of backward edges.
+///
+/// The text form is `(from,to,from_label,e_label,to_label)` with `_` for absent labels,
+/// e.g. `(0,1,3,1,4)` or `(2,0,_,1,_)`.
+#[derive(PartialEq, Eq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DFS {
     pub from: usize,
     pub to: usize,
-    pub from_label: isize,
+    pub from_label: Option<isize>,
     pub e_label: usize,
-    pub to_label: isize,
+    pub to_label: Option<isize>,
+}
+
+impl Default for DFS {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl DFS {
@@ -12,13 +29,19 @@ impl DFS {
         DFS {
             from: 0,
             to: 0,
-            from_label: 0,
+            from_label: None,
             e_label: 0,
-            to_label: 0,
+            to_label: None,
         }
     }
 
-    pub fn from(from: usize, to: usize, from_label: isize, e_label: usize, to_label: isize) -> DFS {
+    pub fn from(
+        from: usize,
+        to: usize,
+        from_label: Option<isize>,
+        e_label: usize,
+        to_label: Option<isize>,
+    ) -> DFS {
         DFS {
             from,
             to,
@@ -27,6 +50,115 @@ impl DFS {
             to_label,
         }
     }
+
+    /// Whether the edge goes back to a vertex which already occurs earlier in the code.
+    pub fn is_backward(&self) -> bool {
+        self.from > self.to
+    }
+}
+
+/// The gSpan DFS lexicographic edge order, for edges following the same code prefix:
+/// backward edges come before forward edges, backward edges are ordered by their to
+/// vertex, forward edges start at the deepest from vertex first, and ties are broken by
+/// the labels. The remaining fields only keep the order total.
+impl Ord for DFS {
+    fn cmp(&self, other: &DFS) -> Ordering {
+        match (self.is_backward(), other.is_backward()) {
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+            (true, true) => (
+                self.to,
+                self.e_label,
+                self.from,
+                self.from_label,
+                self.to_label,
+            )
+                .cmp(&(
+                    other.to,
+                    other.e_label,
+                    other.from,
+                    other.from_label,
+                    other.to_label,
+                )),
+            (false, false) => other.from.cmp(&self.from).then_with(|| {
+                (self.from_label, self.e_label, self.to_label, self.to).cmp(&(
+                    other.from_label,
+                    other.e_label,
+                    other.to_label,
+                    other.to,
+                ))
+            }),
+        }
+    }
+}
+
+impl PartialOrd for DFS {
+    fn partial_cmp(&self, other: &DFS) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for DFS {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let label = |label: Option<isize>| match label {
+            Some(label) => label.to_string(),
+            None => "_".to_string(),
+        };
+        write!(
+            f,
+            "({},{},{},{},{})",
+            self.from,
+            self.to,
+            label(self.from_label),
+            self.e_label,
+            label(self.to_label)
+        )
+    }
+}
+
+/// Invalid text form of a [`DFS`] or [`crate::DFSCode`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DFSParseError(pub String);
+
+impl fmt::Display for DFSParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid DFS code: {}", self.0)
+    }
+}
+
+impl std::error::Error for DFSParseError {}
+
+impl FromStr for DFS {
+    type Err = DFSParseError;
+
+    fn from_str(s: &str) -> Result<DFS, DFSParseError> {
+        let invalid = || {
+            DFSParseError(format!(
+                "'{}' is not an edge (from,to,label,label,label)",
+                s
+            ))
+        };
+        let fields = s
+            .trim()
+            .strip_prefix('(')
+            .and_then(|s| s.strip_suffix(')'))
+            .ok_or_else(invalid)?;
+        let fields: Vec<&str> = fields.split(',').map(|field| field.trim()).collect();
+        let [from, to, from_label, e_label, to_label] = fields[..] else {
+            return Err(invalid());
+        };
+        let label = |label: &str| match label {
+            "_" => Ok(None),
+            label => label.parse().map(Some).map_err(|_| invalid()),
+        };
+        Ok(DFS {
+            from: from.parse().map_err(|_| invalid())?,
+            to: to.parse().map_err(|_| invalid())?,
+            from_label: label(from_label)?,
+            e_label: e_label.parse().map_err(|_| invalid())?,
+            to_label: label(to_label)?,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -35,12 +167,39 @@ mod tests {
 
     #[test]
     fn test_equal_dfs() {
-        let dfs1 = DFS::from(1, 2, 3, 4, 5);
-        let dfs2 = DFS::from(1, 2, 3, 4, 5);
-        let dfs3 = DFS::from(2, 2, 3, 4, 5);
+        let dfs1 = DFS::from(1, 2, Some(3), 4, Some(5));
+        let dfs2 = DFS::from(1, 2, Some(3), 4, Some(5));
+        let dfs3 = DFS::from(2, 2, Some(3), 4, Some(5));
+        let dfs4 = DFS::from(1, 2, None, 4, Some(5));
 
         assert_eq!(dfs1, dfs2);
         assert_ne!(dfs1, dfs3);
         assert_ne!(dfs2, dfs3);
+        assert_ne!(dfs1, dfs4);
+    }
+
+    #[test]
+    fn test_edge_order() {
+        let backward = DFS::from(2, 0, None, 1, None);
+        let backward_2 = DFS::from(2, 1, None, 0, None);
+        let forward_deep = DFS::from(2, 3, None, 5, Some(9));
+        let forward = DFS::from(1, 3, None, 0, Some(0));
+        assert!(backward < backward_2);
+        assert!(backward_2 < forward_deep);
+        assert!(forward_deep < forward);
+        assert!(DFS::from(0, 1, Some(1), 2, Some(3)) < DFS::from(0, 1, Some(1), 2, Some(4)));
+    }
+
+    #[test]
+    fn test_text_form() {
+        let dfs = DFS::from(2, 0, None, 1, None);
+        assert_eq!(dfs.to_string(), "(2,0,_,1,_)");
+        assert_eq!("(2,0,_,1,_)".parse::<DFS>(), Ok(dfs));
+        assert_eq!(
+            " ( 0, 1, -1, 2, 3 ) ".parse::<DFS>(),
+            Ok(DFS::from(0, 1, Some(-1), 2, Some(3)))
+        );
+        assert!("(0,1,2,3)".parse::<DFS>().is_err());
+        assert!("(0,1,a,2,3)".parse::<DFS>().is_err());
     }
 }
@@ -1,12 +1,29 @@
-use crate::models::dfs::DFS;
+use crate::models::dfs::{DFSParseError, DFS};
 use crate::models::graph::Graph;
+use rustc_hash::{FxHashMap, FxHashSet};
 use std::cmp;
 use std::cmp::max;
+use std::fmt;
+use std::str::FromStr;
 
+/// A pattern as the sequence of its edges in depth first search order.
+///
+/// Codes are ordered by the gSpan DFS lexicographic order (edge by edge, see the order of
+/// [`DFS`]), so the canonical code of an undirected graph is the smallest of its codes
+/// (see [`Graph::canonical_code`] for directed graphs). The text form is the
+/// concatenation of the edges, e.g. `(0,1,1,0,2)(1,2,_,0,1)(2,0,_,1,_)`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DFSCode {
     pub dfs_vec: Vec<DFS>,
 }
 
+impl Default for DFSCode {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl DFSCode {
     pub fn new() -> DFSCode {
         DFSCode {
@@ -18,29 +35,94 @@ impl DFSCode {
         &mut self,
         from: usize,
         to: usize,
-        from_label: isize,
+        from_label: Option<isize>,
         e_label: usize,
-        to_label: isize,
+        to_label: Option<isize>,
     ) {
         self.dfs_vec
             .push(DFS::from(from, to, from_label, e_label, to_label))
     }
 
     pub fn pop(&mut self) -> Option<DFS> {
-        return self.dfs_vec.pop();
+        self.dfs_vec.pop()
+    }
+
+    /// Returns a DFS code of the graph found by a depth first traversal, which is not
+    /// necessarily the minimum code. Returns `None` if the graph has no edges, is not
+    /// connected, or (for directed graphs) no traversal along the edge directions can
+    /// describe it.
+    pub fn from_graph(graph: &Graph) -> Option<DFSCode> {
+        let mut graph = graph.clone();
+        graph.build_edge();
+        if graph.edge_size == 0 {
+            return None;
+        }
+        (0..graph.vertices.len()).find_map(|root| {
+            let mut traversal = Traversal {
+                graph: &graph,
+                index: vec![None; graph.vertices.len()],
+                on_path: vec![false; graph.vertices.len()],
+                used: FxHashSet::default(),
+                code: DFSCode::new(),
+            };
+            traversal.index[root] = Some(0);
+            let complete = traversal.visit(root)
+                && traversal.used.len() == graph.edge_size
+                && traversal.index.iter().all(|index| index.is_some());
+            complete.then_some(traversal.code)
+        })
     }
 
+    fn validate(&self) -> Result<(), String> {
+        let mut nodes = 0;
+        for (i, dfs) in self.dfs_vec.iter().enumerate() {
+            let valid = if i == 0 {
+                nodes = 2;
+                dfs.from.min(dfs.to) == 0
+                    && dfs.from.max(dfs.to) == 1
+                    && dfs.from_label.is_some()
+                    && dfs.to_label.is_some()
+            } else if dfs.to == nodes {
+                nodes += 1;
+                dfs.from < dfs.to && dfs.from_label.is_none() && dfs.to_label.is_some()
+            } else if dfs.from == nodes {
+                // Edge of a directed canonical code against the traversal
+                nodes += 1;
+                dfs.to < dfs.from && dfs.from_label.is_some() && dfs.to_label.is_none()
+            } else {
+                dfs.from < nodes
+                    && dfs.to < nodes
+                    && dfs.from != dfs.to
+                    && dfs.from_label.is_none()
+                    && dfs.to_label.is_none()
+            };
+            if !valid {
+                return Err(format!(
+                    "edge {} ({}) does not continue the code",
+                    i + 1,
+                    dfs
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the pattern described by the code into the empty graph `g`.
+    ///
+    /// With `single_nodes`, all vertices with the same label are merged into one vertex,
+    /// in the order their labels first occur in the code. Edges between merged vertices
+    /// become self loops and parallel edges with the same label are only added once.
     pub fn to_graph(&self, g: &mut Graph, single_nodes: bool) {
         // Version 1: Multiple nodes for nodes with same label
         if !single_nodes {
             for it in &self.dfs_vec {
                 g.resize(cmp::max(it.from, it.to) + 1);
 
-                if it.from_label != -1 {
-                    g.vertices.get_mut(it.from).unwrap().label = it.from_label;
+                if let Some(label) = it.from_label {
+                    g.vertices.get_mut(it.from).unwrap().label = label;
                 }
-                if it.to_label != -1 {
-                    g.vertices.get_mut(it.to).unwrap().label = it.to_label;
+                if let Some(label) = it.to_label {
+                    g.vertices.get_mut(it.to).unwrap().label = label;
                 }
                 g.vertices.get_mut(it.from).unwrap().push(it.to, it.e_label);
                 if !g.directed {
@@ -50,7 +132,34 @@ impl DFSCode {
         } else {
             // Version 2: One node for nodes with the same label
             // Create label mapping
-            todo!()
+            let mut labels: Vec<isize> = vec![0; self.count_node()];
+            for it in &self.dfs_vec {
+                if let Some(label) = it.from_label {
+                    labels[it.from] = label;
+                }
+                if let Some(label) = it.to_label {
+                    labels[it.to] = label;
+                }
+            }
+            let mut nodes: FxHashMap<isize, usize> = FxHashMap::default();
+            let mut node_of = |g: &mut Graph, label: isize| {
+                *nodes.entry(label).or_insert_with(|| g.add_vertex(label))
+            };
+            for it in &self.dfs_vec {
+                let from = node_of(g, labels[it.from]);
+                let to = node_of(g, labels[it.to]);
+                let exists = g.vertices[from]
+                    .edges
+                    .iter()
+                    .any(|e| e.to == to && e.e_label == it.e_label);
+                if exists {
+                    continue;
+                }
+                g.vertices[from].push(to, it.e_label);
+                if !g.directed && from != to {
+                    g.vertices[to].push(from, it.e_label);
+                }
+            }
         }
         g.build_edge();
     }
@@ -76,3 +185,210 @@ impl DFSCode {
         count
     }
 }
+
+impl fmt::Display for DFSCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for dfs in &self.dfs_vec {
+            write!(f, "{}", dfs)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses the text form, the edges have to form a valid DFS code: the first edge is
+/// `(0,1,..)` with both labels, every forward edge discovers the next vertex and has a to
+/// label, and all other labels are `_`. Edges of directed canonical codes may also point
+/// against the traversal, see [`Graph::canonical_code`].
+impl FromStr for DFSCode {
+    type Err = DFSParseError;
+
+    fn from_str(s: &str) -> Result<DFSCode, DFSParseError> {
+        let mut code = DFSCode::new();
+        let mut rest = s.trim();
+        while !rest.is_empty() {
+            let Some(end) = rest.find(')') else {
+                return Err(DFSParseError(format!("missing ')' in '{}'", rest)));
+            };
+            code.dfs_vec.push(rest[..=end].parse()?);
+            rest = rest[end + 1..].trim_start();
+        }
+        code.validate().map_err(DFSParseError)?;
+        Ok(code)
+    }
+}
+
+/// State of [`DFSCode::from_graph`].
+struct Traversal<'g> {
+    graph: &'g Graph,
+    /// Index of the vertices in the code, `None` if not visited yet.
+    index: Vec<Option<usize>>,
+    /// Whether the vertex is on the path from the root to the current vertex.
+    on_path: Vec<bool>,
+    /// Ids of the edges already in the code.
+    used: FxHashSet<usize>,
+    code: DFSCode,
+}
+
+impl Traversal<'_> {
+    /// Adds the backward edges of the newly visited vertex `v`, then the forward edges of
+    /// its subtree. Returns false if an edge can not be added to the code.
+    fn visit(&mut self, v: usize) -> bool {
+        let graph = self.graph;
+        let from = self.index[v].unwrap();
+        let mut backward: Vec<(usize, usize, usize)> = Vec::new();
+        for edge in &graph.vertices[v].edges {
+            if let Some(to) = self.index[edge.to] {
+                if self.used.contains(&edge.id) {
+                    continue;
+                }
+                // Backward edges may only go to the vertices on the rightmost path
+                if !self.on_path[edge.to] {
+                    return false;
+                }
+                backward.push((to, edge.e_label, edge.id));
+            }
+        }
+        backward.sort();
+        for (to, e_label, id) in backward {
+            self.used.insert(id);
+            self.code.push(from, to, None, e_label, None);
+        }
+        self.on_path[v] = true;
+        for edge in &graph.vertices[v].edges {
+            if self.used.contains(&edge.id) {
+                continue;
+            }
+            if self.index[edge.to].is_some() {
+                // Edge to a vertex of an earlier subtree, only possible in directed graphs
+                return false;
+            }
+            let to = self.code.count_node().max(1);
+            let from_label = self
+                .code
+                .dfs_vec
+                .is_empty()
+                .then_some(graph.vertices[v].label);
+            self.index[edge.to] = Some(to);
+            self.used.insert(edge.id);
+            let to_label = graph.vertices[edge.to].label;
+            self.code
+                .push(from, to, from_label, edge.e_label, Some(to_label));
+            if !self.visit(edge.to) {
+                return false;
+            }
+        }
+        self.on_path[v] = false;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::graph::GraphBuilder;
+
+    #[test]
+    fn test_to_graph_single_nodes() {
+        // Path 1 - 2 - 1 - 2 with edge label 7 and an edge with label 8 between both 2s
+        let mut code = DFSCode::new();
+        code.push(0, 1, Some(1), 7, Some(2));
+        code.push(1, 2, None, 7, Some(1));
+        code.push(2, 3, None, 7, Some(2));
+        code.push(3, 1, None, 8, None);
+
+        let mut g = Graph::new(0, false);
+        code.to_graph(&mut g, false);
+        assert_eq!(g.vertices.len(), 4);
+
+        let mut g = Graph::new(0, false);
+        code.to_graph(&mut g, true);
+        let labels: Vec<isize> = g.vertices.iter().map(|v| v.label).collect();
+        assert_eq!(labels, vec![1, 2]);
+        assert_eq!(g.edge_size, 2);
+        assert_eq!(
+            g.to_str_repr(None),
+            "t # 0\nv 0 1\nv 1 2\ne 0 1 7\ne 1 0 7\ne 1 1 8"
+        );
+    }
+
+    #[test]
+    fn test_text_form() {
+        let text = "(0,1,1,0,2)(1,2,_,0,1)(2,0,_,1,_)";
+        let code: DFSCode = text.parse().unwrap();
+        assert_eq!(code.dfs_vec.len(), 3);
+        assert_eq!(code.to_string(), text);
+        assert_eq!("".parse::<DFSCode>(), Ok(DFSCode::new()));
+        // The second edge has to discover vertex 2
+        assert!("(0,1,1,0,2)(1,3,_,0,1)".parse::<DFSCode>().is_err());
+        assert!("(0,1,1,0,2)(1,2,_,0,1".parse::<DFSCode>().is_err());
+    }
+
+    #[test]
+    fn test_code_order() {
+        let codes = [
+            "(0,1,1,0,1)(1,2,_,0,1)(2,0,_,0,_)",
+            "(0,1,1,0,1)(1,2,_,0,1)(2,3,_,0,1)",
+            "(0,1,1,0,1)(1,2,_,0,1)(0,3,_,0,1)",
+            "(0,1,1,0,1)(0,2,_,0,1)",
+            "(0,1,1,0,2)",
+        ];
+        let codes: Vec<DFSCode> = codes.iter().map(|c| c.parse().unwrap()).collect();
+        for pair in codes.windows(2) {
+            assert!(pair[0] < pair[1], "{} < {}", pair[0], pair[1]);
+        }
+        let prefix: DFSCode = "(0,1,1,0,1)".parse().unwrap();
+        assert!(prefix < codes[0]);
+    }
+
+    #[test]
+    fn test_from_graph() {
+        let graph = GraphBuilder::new(0, false)
+            .vertex(1)
+            .vertex(2)
+            .vertex(3)
+            .vertex(4)
+            .edge(0, 1, 5)
+            .edge(1, 2, 6)
+            .edge(2, 0, 7)
+            .edge(2, 3, 8)
+            .build()
+            .unwrap();
+        let code = DFSCode::from_graph(&graph).unwrap();
+        assert_eq!(
+            code.to_string(),
+            "(0,1,1,5,2)(1,2,_,6,3)(2,0,_,7,_)(2,3,_,8,4)"
+        );
+        assert_eq!(code.to_string().parse::<DFSCode>(), Ok(code.clone()));
+        let mut copy = Graph::new(0, false);
+        code.to_graph(&mut copy, false);
+        assert_eq!(DFSCode::from_graph(&copy), Some(code));
+
+        let disconnected = GraphBuilder::new(0, false)
+            .vertex(1)
+            .vertex(1)
+            .vertex(1)
+            .edge(0, 1, 0)
+            .build()
+            .unwrap();
+        assert_eq!(DFSCode::from_graph(&disconnected), None);
+
+        // 0 -> 1 <- 2 can not be described starting at any vertex
+        let directed = GraphBuilder::new(0, true)
+            .vertex(1)
+            .vertex(1)
+            .vertex(1)
+            .edge(0, 1, 0)
+            .edge(2, 1, 0)
+            .build()
+            .unwrap();
+        assert_eq!(DFSCode::from_graph(&directed), None);
+        let path = GraphBuilder::new(0, true)
+            .vertex(1)
+            .vertex(2)
+            .edge(1, 0, 0)
+            .build()
+            .unwrap();
+        let code = DFSCode::from_graph(&path).unwrap();
+        assert_eq!(code.to_string(), "(0,1,2,0,1)");
+    }
+}
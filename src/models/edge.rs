@@ -1,7 +1,9 @@
-use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::models::labels::LabelDictionary;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Edge {
+    /// Id of the edge inside its graph, both directions of an undirected edge share the id.
     pub id: usize,
     pub from: usize,
     pub to: usize,
@@ -9,17 +11,18 @@ pub struct Edge {
 }
 
 impl Edge {
+    /// Creates an edge with the id 0, the id is assigned by [`crate::Graph::add_edge`]
+    /// or [`crate::Graph::build_edge`].
     pub fn new(from: usize, to: usize, e_label: usize) -> Edge {
-        static COUNTER: AtomicUsize = AtomicUsize::new(1);
         Edge {
-            id: COUNTER.fetch_add(1, Ordering::Relaxed),
+            id: 0,
             from,
             to,
             e_label,
         }
     }
     pub fn to_str_repr(&self) -> String {
-        vec![
+        [
             "e".to_string(),
             self.from.to_string(),
             self.to.to_string(),
@@ -27,6 +30,14 @@ impl Edge {
         ]
         .join(" ")
     }
+
+    /// Like [`Edge::to_str_repr`], but with the string label from the dictionary.
+    pub fn to_str_repr_with_labels(&self, labels: &LabelDictionary) -> String {
+        match labels.edge_label(self.e_label) {
+            Some(label) => ["e", &self.from.to_string(), &self.to.to_string(), label].join(" "),
+            None => self.to_str_repr(),
+        }
+    }
 }
 
 impl PartialEq for Edge {
@@ -1,24 +1,40 @@
+use crate::canonical::{min_dfs_code, min_directed_code};
+use crate::error::Error;
+use crate::models::dfs_code::DFSCode;
 use crate::models::edge::Edge;
+use crate::models::labels::LabelDictionary;
 use crate::models::vertex::Vertex;
-use std::fs::File;
-use std::io::BufRead;
+pub use crate::parser::GraphSetParseError;
+use crate::parser::GraphSetParser;
+use rustc_hash::FxHashMap;
+use std::fmt;
 use std::path::Path;
-use std::{fmt, io};
 
-#[derive(Debug)]
-pub struct GraphSetParseError {
-    message: String,
+/// Errors of the graph construction API.
+#[derive(Debug, PartialEq)]
+pub enum GraphError {
+    /// The graph has no vertex with the given id.
+    VertexNotFound(usize),
 }
 
-impl fmt::Display for GraphSetParseError {
+impl fmt::Display for GraphError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.message)
+        match self {
+            GraphError::VertexNotFound(id) => write!(f, "Vertex {} not found", id),
+        }
     }
 }
 
-#[derive(Debug)]
+impl std::error::Error for GraphError {}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Graph {
     pub id: usize,
+    /// Id of the graph in the input file, differs from `id` if the graph was remapped
+    /// by the lenient parser.
+    pub original_id: usize,
+    /// Number of edges, edge ids are `0..edge_size`.
     pub edge_size: usize,
     pub directed: bool,
     pub vertices: Vec<Vertex>,
@@ -28,6 +44,7 @@ impl Graph {
     pub fn new(id: usize, directed: bool) -> Graph {
         Graph {
             id,
+            original_id: id,
             edge_size: 0,
             directed,
             vertices: Vec::with_capacity(32),
@@ -45,249 +62,393 @@ impl Graph {
     }
 
     pub fn resize(&mut self, size: usize) {
-        while &self.vertices.len() < &size {
+        while self.vertices.len() < size {
             self.create_vertex();
         }
     }
 
     pub fn has_vertex_with_id(&self, id: &usize) -> bool {
-        return self.vertices.len() > *id;
+        self.vertices.len() > *id
     }
 
-    pub fn build_edge(&self) {
-        //todo!()
+    /// Adds a vertex with the given label and returns its id.
+    pub fn add_vertex(&mut self, label: isize) -> usize {
+        let vertex = self.create_vertex();
+        vertex.label = label;
+        vertex.id
     }
 
-    pub fn graphs_set_from_file<P>(
-        path: P,
-        directed: bool,
-    ) -> Result<Vec<Graph>, GraphSetParseError>
-        where
-            P: AsRef<Path>,
-    {
-        let mut graph_list = Vec::new();
-        let mut graph_id = 0;
-        let mut current_graph: Graph = Graph::new(usize::MAX, directed);
-        let line_reader = read_lines(path);
-        match line_reader {
-            Ok(lines) => {
-                for line in lines {
-                    if let Ok(data_line) = line {
-                        let mut data = data_line.split(" ");
-                        if let Some(data_type) = data.next() {
-                            match data_type {
-                                "t" => {
-                                    let _ = data.next().ok_or(GraphSetParseError {
-                                        message: "Missing '#' in graph".to_string(),
-                                    })?;
-                                    let id = data.next().ok_or(GraphSetParseError {
-                                        message: "Id for graph is missing".to_string(),
-                                    })?;
-                                    if id == "-1" {
-                                        break;
-                                    }
-                                    if current_graph.id != usize::MAX {
-                                        graph_list.push(current_graph);
-                                    }
-                                    let id = id.parse::<usize>();
-                                    match id {
-                                        Ok(id) => {
-                                            current_graph = Graph::new(id, directed);
-                                            if id != graph_id {
-                                                return Err(GraphSetParseError {
-                                                    message: format!("Graph with graph id {}, it should have the id {}", id, graph_id),
-                                                });
-                                            }
-                                            graph_id += 1;
-                                        },
-                                        _ => {
-                                            return Err(GraphSetParseError {
-                                                message: "Id for graph invalid".to_string(),
-                                            });
-                                        }
-                                    }
-                                }
-                                "v" => {
-                                    let id = data.next().ok_or(GraphSetParseError {
-                                        message: format!(
-                                            "Graph {}, Missing id for a vertex in",
-                                            current_graph.id.to_string()
-                                        )
-                                            .to_string(),
-                                    })?;
-                                    let id = id.parse::<usize>();
-                                    match id {
-                                        Ok(id) => {
-                                            let vertex = current_graph.create_vertex();
-                                            if vertex.id != id {
-                                                return Err(GraphSetParseError {
-                                                    message: format!("Graph {}, Vertex ID ({}) in input file does not fit the expected ID {}", current_graph.id.to_string(), id.to_string(), current_graph.get_last_vertex().id.to_string()).to_string()
-                                                });
-                                            }
-                                            let label = data.next().ok_or(GraphSetParseError {
-                                                message: format!(
-                                                    "Graph {}, Missing label for a vertex",
-                                                    current_graph.id.to_string()
-                                                )
-                                                    .to_string(),
-                                            })?;
-                                            let label = label.parse::<isize>();
-                                            if label.is_err() {
-                                                return Err(GraphSetParseError {
-                                                    message: format!(
-                                                        "Graph {}, Vertex {}, Label invalid",
-                                                        current_graph.id.to_string(),
-                                                        id.to_string()
-                                                    ),
-                                                });
-                                            }
-                                            current_graph.get_last_vertex().label = label.unwrap();
-                                        }
-                                        _ => {
-                                            return Err(GraphSetParseError {
-                                                message: format!(
-                                                    "Graph {}, Vertex ID invalid",
-                                                    current_graph.id.to_string()
-                                                ).to_string(),
-                                            });
-                                        }
-                                    }
-                                }
-                                "e" => {
-                                    let from_id = data.next().ok_or(GraphSetParseError {
-                                        message: format!(
-                                            "Graph {}, Missing from id for an edge",
-                                            current_graph.id.to_string()
-                                        )
-                                            .to_string(),
-                                    })?;
-                                    let from_id: usize = match from_id.parse() {
-                                        Ok(value) => value,
-                                        _ => {
-                                            return Err(GraphSetParseError {
-                                                message: format!(
-                                                    "Graph {}, Invalid from id for an edge",
-                                                    current_graph.id.to_string()
-                                                )
-                                                    .to_string(),
-                                            });
-                                        }
-                                    };
-                                    let to_id = data.next().ok_or(GraphSetParseError {
-                                        message: format!(
-                                            "Graph {}, Missing to id for a edge in",
-                                            current_graph.id.to_string()
-                                        )
-                                            .to_string(),
-                                    })?;
-                                    let to_id: usize = match to_id.parse() {
-                                        Ok(value) => value,
-                                        _ => {
-                                            return Err(GraphSetParseError {
-                                                message: format!(
-                                                    "Graph {}, Invalid to id for a edge",
-                                                    current_graph.id.to_string()
-                                                ).to_string(),
-                                            });
-                                        }
-                                    };
-                                    let e_label = data.next().ok_or(GraphSetParseError {
-                                        message: format!(
-                                            "Graph {}, Missing edge label for a edge",
-                                            current_graph.id.to_string()
-                                        ).to_string(),
-                                    })?;
-                                    let e_label: usize = match e_label.parse() {
-                                        Ok(value) => value,
-                                        _ => {
-                                            return Err(GraphSetParseError {
-                                                message: format!(
-                                                    "Graph {}, Invalid e_label for a edge",
-                                                    current_graph.id.to_string()
-                                                ).to_string(),
-                                            });
-                                        }
-                                    };
-
-                                    if !current_graph.has_vertex_with_id(&from_id)
-                                        || !current_graph.has_vertex_with_id(&to_id)
-                                    {
-                                        return Err(GraphSetParseError {
-                                            message: format!(
-                                                "Graph {}, Edge invalid, ids of vertices not found",
-                                                current_graph.id.to_string()
-                                            ).to_string(),
-                                        });
-                                    }
-
-                                    let from_vertex: Option<&mut Vertex> =
-                                        current_graph.vertices.get_mut(from_id);
-                                    match from_vertex {
-                                        Some(from_vertex) => {
-                                            from_vertex.push(to_id, e_label);
-                                        }
-                                        _ => return Err(GraphSetParseError {
-                                            message: format!(
-                                                "Graph {}, Edge invalid, ids of vertices not found",
-                                                current_graph.id.to_string()
-                                            )
-                                                .to_string(),
-                                        }),
-                                    }
-                                    if !directed {
-                                        let from_vertex: Option<&mut Vertex> =
-                                            current_graph.vertices.get_mut(to_id);
-                                        match from_vertex {
-                                            Some(from_vertex) => {
-                                                from_vertex.push(from_id, e_label);
-                                            }
-                                            _ => return Err(GraphSetParseError {
-                                                message: format!("Graph {}, Edge invalid, ids of vertices not found", current_graph.id.to_string()).to_string()
-                                            })
-                                        }
-                                    }
-                                }
-                                _ => {}
-                            }
-                        }
-                    }
-                }
+    /// Adds an edge between two existing vertices. For undirected graphs the reverse
+    /// edge is added as well.
+    ///
+    /// Self loops (`from == to`) are kept, but skipped by the miner and by
+    /// [`Graph::canonical_code`]; only [`crate::find_matches`] takes them into account.
+    /// Parallel edges are kept as separate edges with their own ids, also if they have
+    /// the same label. Mined patterns have at most one edge between two vertices, so
+    /// parallel edges only count as one edge of any of their labels.
+    pub fn add_edge(&mut self, from: usize, to: usize, e_label: usize) -> Result<(), GraphError> {
+        for id in [from, to] {
+            if !self.has_vertex_with_id(&id) {
+                return Err(GraphError::VertexNotFound(id));
             }
-            Err(_) => {
-                return Err(GraphSetParseError {
-                    message: "Error reading file".to_string(),
-                });
+        }
+        let id = self.edge_size;
+        self.edge_size += 1;
+        self.vertices[from].push(to, e_label);
+        self.vertices[from].edges.last_mut().unwrap().id = id;
+        if !self.directed {
+            self.vertices[to].push(from, e_label);
+            self.vertices[to].edges.last_mut().unwrap().id = id;
+        }
+        Ok(())
+    }
+
+    /// Assigns the edge ids `0..edge_size` to all edges of the graph. Both directions of
+    /// an undirected edge get the same id. Only needed if edges were pushed to the
+    /// vertices directly instead of using [`Graph::add_edge`].
+    pub fn build_edge(&mut self) {
+        let mut ids: FxHashMap<(usize, usize, usize), usize> = FxHashMap::default();
+        for vertex in self.vertices.iter_mut() {
+            for edge in vertex.edges.iter_mut() {
+                let key = if self.directed || edge.from <= edge.to {
+                    (edge.from, edge.to, edge.e_label)
+                } else {
+                    (edge.to, edge.from, edge.e_label)
+                };
+                let next_id = ids.len();
+                edge.id = *ids.entry(key).or_insert(next_id);
             }
         }
-        if current_graph.id != usize::MAX {
-            graph_list.push(current_graph);
+        self.edge_size = ids.len();
+    }
+
+    /// Parses a graph database file, see [`GraphSetParser`] for more options.
+    pub fn graphs_set_from_file<P>(path: P, directed: bool) -> Result<Vec<Graph>, Error>
+    where
+        P: AsRef<Path>,
+    {
+        GraphSetParser::new(directed).parse_file(path)
+    }
+
+    /// Returns the minimum DFS code of the graph, which is the same for all isomorphic
+    /// graphs. Returns `None` if the graph has no edges, is not connected or has self loops.
+    ///
+    /// The code of a directed graph may walk edges against their direction, unlike the
+    /// codes of the miner. Such edges are written with their actual direction, e.g.
+    /// `(1,0,2,0,1)` for an edge from the new vertex 1 to the vertex 0, and come after
+    /// edges along their direction that are otherwise equal.
+    pub fn canonical_code(&self) -> Option<DFSCode> {
+        let mut graph = self.clone();
+        graph.build_edge();
+        let code = if graph.directed {
+            min_directed_code(&graph)?
+        } else {
+            min_dfs_code(&graph, None)?
+        };
+        let complete =
+            code.dfs_vec.len() == graph.edge_size && code.count_node() == graph.vertices.len();
+        complete.then_some(code)
+    }
+
+    /// Whether both graphs are equal up to the vertex ids, see [`Graph::canonical_code`].
+    /// Graphs without edges are compared by their vertex labels. Returns false if either
+    /// graph has no canonical code otherwise.
+    pub fn is_isomorphic(&self, other: &Graph) -> bool {
+        if self.directed != other.directed || self.vertices.len() != other.vertices.len() {
+            return false;
         }
-        Ok(graph_list)
+        let no_edges = |g: &Graph| g.vertices.iter().all(|v| v.edges.is_empty());
+        if no_edges(self) || no_edges(other) {
+            let labels = |g: &Graph| {
+                let mut labels: Vec<isize> = g.vertices.iter().map(|v| v.label).collect();
+                labels.sort_unstable();
+                labels
+            };
+            return no_edges(self) && no_edges(other) && labels(self) == labels(other);
+        }
+        match (self.canonical_code(), other.canonical_code()) {
+            (Some(code), Some(other_code)) => code == other_code,
+            _ => false,
+        }
+    }
+
+    /// 64-bit FNV-1a hash of the text form of the canonical code, stable across runs,
+    /// platforms and versions of this crate. `None` if there is no canonical code.
+    pub fn canonical_hash(&self) -> Option<u64> {
+        let code = self.canonical_code()?;
+        let mut text = code.to_string();
+        if self.directed {
+            text.push('d');
+        }
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for byte in text.bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+        Some(hash)
     }
 
     pub fn to_str_repr(&self, support: Option<usize>) -> String {
+        self.str_repr(support, None)
+    }
+
+    /// Like [`Graph::to_str_repr`], but with the string labels from the dictionary.
+    pub fn to_str_repr_with_labels(
+        &self,
+        support: Option<usize>,
+        labels: &LabelDictionary,
+    ) -> String {
+        self.str_repr(support, Some(labels))
+    }
+
+    fn str_repr(&self, support: Option<usize>, labels: Option<&LabelDictionary>) -> String {
         let mut lines: Vec<String> = Vec::new();
-        let mut g_rep = format!("t # {}", self.id.to_string());
+        let mut g_rep = format!("t # {}", self.id);
         if let Some(support) = support {
             g_rep += &*format!(" * {}", support);
         }
         lines.push(g_rep);
         let mut edges: Vec<&Edge> = Vec::new();
         for vertex in &self.vertices {
-            lines.push(vertex.to_str_repr());
+            lines.push(match labels {
+                Some(labels) => vertex.to_str_repr_with_labels(labels),
+                None => vertex.to_str_repr(),
+            });
             edges.extend(vertex.edges.iter());
         }
         for edge in edges {
-            lines.push(edge.to_str_repr());
+            lines.push(match labels {
+                Some(labels) => edge.to_str_repr_with_labels(labels),
+                None => edge.to_str_repr(),
+            });
         }
         lines.join("\n")
     }
 }
 
-fn read_lines<P>(filename: P) -> io::Result<io::Lines<io::BufReader<File>>>
-    where
-        P: AsRef<Path>,
-{
-    let file = File::open(filename)?;
-    Ok(io::BufReader::new(file).lines())
+/// Builds a [`Graph`] from vertex labels and edges.
+///
+/// ```
+/// use gspan::GraphBuilder;
+///
+/// let graph = GraphBuilder::new(0, false)
+///     .vertex(1)
+///     .vertex(2)
+///     .edge(0, 1, 3)
+///     .build()
+///     .unwrap();
+/// assert_eq!(graph.vertices.len(), 2);
+/// ```
+pub struct GraphBuilder {
+    graph: Graph,
+    error: Option<GraphError>,
+}
+
+impl GraphBuilder {
+    pub fn new(id: usize, directed: bool) -> GraphBuilder {
+        GraphBuilder {
+            graph: Graph::new(id, directed),
+            error: None,
+        }
+    }
+
+    /// Adds a vertex, vertices get the ids 0, 1, 2, .. in the order they are added.
+    pub fn vertex(mut self, label: isize) -> GraphBuilder {
+        self.graph.add_vertex(label);
+        self
+    }
+
+    /// Adds an edge, see [`Graph::add_edge`].
+    pub fn edge(mut self, from: usize, to: usize, e_label: usize) -> GraphBuilder {
+        if self.error.is_none() {
+            self.error = self.graph.add_edge(from, to, e_label).err();
+        }
+        self
+    }
+
+    /// Returns the graph, or the first error of an invalid edge.
+    pub fn build(self) -> Result<Graph, GraphError> {
+        match self.error {
+            Some(err) => Err(err),
+            None => Ok(self.graph),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_edge() {
+        let mut g = Graph::new(0, false);
+        let v1 = g.add_vertex(1);
+        let v2 = g.add_vertex(2);
+        assert_eq!((v1, v2), (0, 1));
+        g.add_edge(v1, v2, 3).unwrap();
+        assert_eq!(g.vertices[0].edges[0].to, 1);
+        assert_eq!(g.vertices[1].edges[0].to, 0);
+        assert_eq!(g.vertices[1].edges[0].id, g.vertices[0].edges[0].id);
+        assert_eq!(g.edge_size, 1);
+        assert_eq!(g.add_edge(v1, 2, 3), Err(GraphError::VertexNotFound(2)));
+
+        let mut g = Graph::new(0, true);
+        g.add_vertex(1);
+        g.add_vertex(2);
+        g.add_edge(0, 1, 3).unwrap();
+        assert!(g.vertices[1].edges.is_empty());
+    }
+
+    #[test]
+    fn test_self_loop() {
+        let g = GraphBuilder::new(0, false)
+            .vertex(1)
+            .vertex(2)
+            .edge(0, 1, 3)
+            .edge(1, 1, 4)
+            .build()
+            .unwrap();
+        assert_eq!(g.edge_size, 2);
+        assert_eq!(g.canonical_code(), None);
+    }
+
+    #[test]
+    fn test_build_edge() {
+        let mut g = Graph::new(0, false);
+        g.resize(3);
+        for (from, to) in [(0, 1), (1, 2)] {
+            g.vertices[from].push(to, 1);
+            g.vertices[to].push(from, 1);
+        }
+        g.build_edge();
+        assert_eq!(g.edge_size, 2);
+        let ids: Vec<usize> = g
+            .vertices
+            .iter()
+            .flat_map(|v| v.edges.iter().map(|e| e.id))
+            .collect();
+        assert_eq!(ids, vec![0, 0, 1, 1]);
+        let copy = g.clone();
+        assert_eq!(copy.vertices[2].edges[0].id, 1);
+    }
+
+    #[test]
+    fn test_canonical_code() {
+        // The same labelled path 1 - 2 - 3 with a triangle, with different vertex ids
+        let g1 = GraphBuilder::new(0, false)
+            .vertex(1)
+            .vertex(2)
+            .vertex(3)
+            .vertex(2)
+            .edge(0, 1, 0)
+            .edge(1, 2, 0)
+            .edge(2, 3, 1)
+            .edge(3, 1, 1)
+            .build()
+            .unwrap();
+        let g2 = GraphBuilder::new(1, false)
+            .vertex(3)
+            .vertex(2)
+            .vertex(2)
+            .vertex(1)
+            .edge(1, 0, 0)
+            .edge(2, 1, 1)
+            .edge(0, 2, 1)
+            .edge(3, 1, 0)
+            .build()
+            .unwrap();
+        let code = g1.canonical_code().unwrap();
+        assert_eq!(
+            code.to_string(),
+            "(0,1,1,0,2)(1,2,_,0,3)(2,3,_,1,2)(3,1,_,1,_)"
+        );
+        assert_eq!(g2.canonical_code(), Some(code));
+        assert!(g1.is_isomorphic(&g2));
+        assert_eq!(g1.canonical_hash(), g2.canonical_hash());
+
+        let mut g3 = g2.clone();
+        g3.vertices[3].label = 4;
+        assert!(!g1.is_isomorphic(&g3));
+        assert_ne!(g1.canonical_hash(), g3.canonical_hash());
+
+        let single = GraphBuilder::new(0, false).vertex(1).build().unwrap();
+        assert_eq!(single.canonical_code(), None);
+        assert!(single.is_isomorphic(&GraphBuilder::new(5, false).vertex(1).build().unwrap()));
+        assert!(!single.is_isomorphic(&g1));
+    }
+
+    #[test]
+    fn test_canonical_code_directed() {
+        // 2 -> 1 is only reached against its direction from the smaller label
+        let edge = GraphBuilder::new(0, true)
+            .vertex(2)
+            .vertex(1)
+            .edge(0, 1, 0)
+            .build()
+            .unwrap();
+        let code = edge.canonical_code().unwrap();
+        assert_eq!(code.to_string(), "(1,0,2,0,1)");
+        assert_eq!(code.to_string().parse::<DFSCode>(), Ok(code.clone()));
+        let mut g = Graph::new(0, true);
+        code.to_graph(&mut g, false);
+        assert!(g.is_isomorphic(&edge));
+        assert!(edge.is_isomorphic(&edge.clone()));
+
+        // 0 <- 1 -> 0 with different vertex ids, and the reversed 0 -> 1 <- 0
+        let out_star = |center: usize| {
+            let leaves: Vec<usize> = (0..3).filter(|v| *v != center).collect();
+            let mut builder = GraphBuilder::new(0, true);
+            for v in 0..3 {
+                builder = builder.vertex(if v == center { 1 } else { 0 });
+            }
+            builder
+                .edge(center, leaves[0], 0)
+                .edge(center, leaves[1], 0)
+                .build()
+                .unwrap()
+        };
+        let star = out_star(1);
+        assert!(star.canonical_code().is_some());
+        assert!(star.is_isomorphic(&star.clone()));
+        assert!(star.is_isomorphic(&out_star(0)));
+        assert_eq!(star.canonical_hash(), out_star(2).canonical_hash());
+        let in_star = GraphBuilder::new(0, true)
+            .vertex(0)
+            .vertex(1)
+            .vertex(0)
+            .edge(0, 1, 0)
+            .edge(2, 1, 0)
+            .build()
+            .unwrap();
+        assert!(!star.is_isomorphic(&in_star));
+        assert_ne!(star.canonical_hash(), in_star.canonical_hash());
+
+        // A directed cycle and a triangle with one reversed edge, with equal labels
+        let triangle = |reversed: bool| {
+            let builder = GraphBuilder::new(0, true)
+                .vertex(1)
+                .vertex(1)
+                .vertex(1)
+                .edge(0, 1, 0)
+                .edge(1, 2, 0);
+            let builder = if reversed {
+                builder.edge(0, 2, 0)
+            } else {
+                builder.edge(2, 0, 0)
+            };
+            builder.build().unwrap()
+        };
+        assert!(triangle(false).is_isomorphic(&triangle(false)));
+        assert!(!triangle(false).is_isomorphic(&triangle(true)));
+    }
+
+    #[test]
+    fn test_graph_builder() {
+        let err = GraphBuilder::new(0, false)
+            .vertex(1)
+            .edge(0, 1, 1)
+            .edge(0, 0, 1)
+            .build();
+        assert_eq!(err.err(), Some(GraphError::VertexNotFound(1)));
+    }
 }
@@ -9,7 +9,7 @@ pub struct History<'a> {
 }
 
 impl<'a> History<'a> {
-    pub fn build(e: &'a PDFS<'a>) -> History<'a> {
+    pub fn build(e: &PDFS<'a>) -> History<'a> {
         let mut history = History {
             histories: Vec::with_capacity(32),
             edges: FxHashSet::default(),
@@ -31,10 +31,10 @@ impl<'a> History<'a> {
     }
 
     pub fn has_edge(&self, id: &usize) -> bool {
-        self.edges.contains(&id)
+        self.edges.contains(id)
     }
 
     pub fn has_vertex(&self, id: &usize) -> bool {
-        self.vertices.contains(&id)
+        self.vertices.contains(id)
     }
 }
@@ -0,0 +1,71 @@
+use rustc_hash::FxHashMap;
+
+/// Maps string labels to the integer labels used by the miner and back.
+///
+/// Vertex and edge labels are numbered separately in the order they are first seen,
+/// starting at 0.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LabelDictionary {
+    vertex_labels: Vec<String>,
+    vertex_ids: FxHashMap<String, isize>,
+    edge_labels: Vec<String>,
+    edge_ids: FxHashMap<String, usize>,
+}
+
+impl LabelDictionary {
+    pub fn new() -> LabelDictionary {
+        LabelDictionary::default()
+    }
+
+    /// Returns the integer label for a vertex label, adding it if it is new.
+    pub fn vertex(&mut self, label: &str) -> isize {
+        if let Some(id) = self.vertex_ids.get(label) {
+            return *id;
+        }
+        let id = self.vertex_labels.len() as isize;
+        self.vertex_labels.push(label.to_string());
+        self.vertex_ids.insert(label.to_string(), id);
+        id
+    }
+
+    /// Returns the integer label for an edge label, adding it if it is new.
+    pub fn edge(&mut self, label: &str) -> usize {
+        if let Some(id) = self.edge_ids.get(label) {
+            return *id;
+        }
+        let id = self.edge_labels.len();
+        self.edge_labels.push(label.to_string());
+        self.edge_ids.insert(label.to_string(), id);
+        id
+    }
+
+    pub fn vertex_label(&self, id: isize) -> Option<&str> {
+        usize::try_from(id)
+            .ok()
+            .and_then(|id| self.vertex_labels.get(id))
+            .map(|label| label.as_str())
+    }
+
+    pub fn edge_label(&self, id: usize) -> Option<&str> {
+        self.edge_labels.get(id).map(|label| label.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intern_labels() {
+        let mut labels = LabelDictionary::new();
+        assert_eq!(labels.vertex("C"), 0);
+        assert_eq!(labels.vertex("N"), 1);
+        assert_eq!(labels.vertex("C"), 0);
+        assert_eq!(labels.edge("CALL"), 0);
+        assert_eq!(labels.vertex_label(1), Some("N"));
+        assert_eq!(labels.vertex_label(-1), None);
+        assert_eq!(labels.edge_label(0), Some("CALL"));
+        assert_eq!(labels.edge_label(1), None);
+    }
+}
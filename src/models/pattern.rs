@@ -0,0 +1,46 @@
+use crate::matcher::Match;
+use crate::models::dfs_code::DFSCode;
+use crate::models::graph::Graph;
+use crate::models::labels::LabelDictionary;
+
+/// A frequent subgraph found by the miner.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pattern {
+    /// The pattern as graph, using the same id as in the text output.
+    pub graph: Graph,
+    /// The minimum DFS code of the pattern (empty for single vertex patterns).
+    pub dfs_code: DFSCode,
+    /// Number of graphs of the database containing the pattern, also for single vertex
+    /// patterns.
+    pub support: usize,
+    /// Original ids (see [`Graph::original_id`]) of the graphs of the database containing
+    /// the pattern, in the order of the database.
+    pub graph_ids: Vec<usize>,
+    /// Occurrences of the pattern per supporting graph, only collected if enabled with
+    /// [`crate::GSpanConfigBuilder::embeddings`]. The mappings are indexed by the vertices
+    /// of the DFS code, which are the vertices of `graph` unless they were merged.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub embeddings: Vec<Match>,
+}
+
+impl Pattern {
+    pub fn new(graph: Graph, dfs_code: DFSCode, support: usize, graph_ids: Vec<usize>) -> Pattern {
+        Pattern {
+            graph,
+            dfs_code,
+            support,
+            graph_ids,
+            embeddings: Vec::new(),
+        }
+    }
+
+    pub fn to_str_repr(&self) -> String {
+        self.graph.to_str_repr(Some(self.support))
+    }
+
+    pub fn to_str_repr_with_labels(&self, labels: &LabelDictionary) -> String {
+        self.graph
+            .to_str_repr_with_labels(Some(self.support), labels)
+    }
+}
@@ -1,20 +1,18 @@
 use crate::models::edge::Edge;
+use std::rc::Rc;
 
 pub struct PDFS<'a> {
     pub id: usize,
     pub edge: &'a Edge,
-    pub prev: Option<Box<&'a PDFS<'a>>>,
+    pub prev: Option<Rc<PDFS<'a>>>,
 }
 
 impl<'a> PDFS<'a> {
-    pub fn new(id: usize, edge: &'a Edge, prev: Option<&'a PDFS<'a>>) -> PDFS<'a> {
+    pub fn new(id: usize, edge: &'a Edge, prev: Option<&Rc<PDFS<'a>>>) -> PDFS<'a> {
         PDFS {
             id,
             edge,
-            prev: match prev {
-                Some(prev) => Some(Box::new(prev)),
-                None => None,
-            },
+            prev: prev.cloned(),
         }
     }
 }
@@ -1,8 +1,15 @@
 use crate::models::edge::Edge;
 use crate::models::pdfs::PDFS;
+use std::rc::Rc;
 
 pub struct Projected<'a> {
-    pub projections: Vec<Box<PDFS<'a>>>,
+    pub projections: Vec<Rc<PDFS<'a>>>,
+}
+
+impl<'a> Default for Projected<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<'a> Projected<'a> {
@@ -11,8 +18,15 @@ impl<'a> Projected<'a> {
             projections: Vec::with_capacity(32),
         }
     }
-    pub fn push(&mut self, id: usize, edge: &'a Edge, prev: Option<&'a PDFS<'a>>) {
+    pub fn push(&mut self, id: usize, edge: &'a Edge, prev: Option<&Rc<PDFS<'a>>>) {
         let new_pdfs = PDFS::new(id, edge, prev);
-        self.projections.push(Box::new(new_pdfs));
+        self.projections.push(Rc::new(new_pdfs));
+    }
+
+    /// Ids of the graphs with at least one projection, in ascending order.
+    pub fn graph_ids(&self) -> Vec<usize> {
+        let mut ids: Vec<usize> = self.projections.iter().map(|p| p.id).collect();
+        ids.dedup();
+        ids
     }
 }
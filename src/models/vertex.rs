@@ -1,8 +1,13 @@
 use crate::models::edge::Edge;
+use crate::models::labels::LabelDictionary;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vertex {
     pub id: usize,
+    /// Id of the vertex in the input file, differs from `id` if the vertex was remapped
+    /// by the lenient parser.
+    pub original_id: usize,
     pub label: isize,
     pub edges: Vec<Edge>,
 }
@@ -11,10 +16,8 @@ impl Vertex {
     pub fn new(id: usize, label: Option<isize>) -> Vertex {
         Vertex {
             id,
-            label: match label {
-                None => 0,
-                Some(label) => label,
-            },
+            original_id: id,
+            label: label.unwrap_or_default(),
             edges: Vec::with_capacity(8),
         }
     }
@@ -24,7 +27,15 @@ impl Vertex {
     }
 
     pub fn to_str_repr(&self) -> String {
-        vec!["v".to_string(), self.id.to_string(), self.label.to_string()].join(" ")
+        ["v".to_string(), self.id.to_string(), self.label.to_string()].join(" ")
+    }
+
+    /// Like [`Vertex::to_str_repr`], but with the string label from the dictionary.
+    pub fn to_str_repr_with_labels(&self, labels: &LabelDictionary) -> String {
+        match labels.vertex_label(self.label) {
+            Some(label) => ["v", &self.id.to_string(), label].join(" "),
+            None => self.to_str_repr(),
+        }
     }
 }
 
@@ -0,0 +1,473 @@
+use crate::error::Error;
+use crate::models::graph::Graph;
+use crate::models::labels::LabelDictionary;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+
+/// A malformed line of a graph database.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphSetParseError {
+    /// Name of the parsed file, or the source given to [`GraphSetParser::parse_reader`].
+    pub source: String,
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column of the offending token.
+    pub column: usize,
+    /// The raw line.
+    pub text: String,
+    pub message: String,
+}
+
+impl fmt::Display for GraphSetParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{}:{}:{}: {}",
+            self.source, self.line, self.column, self.message
+        )?;
+        writeln!(f, "    {}", self.text)?;
+        write!(f, "    {:>width$}", "^", width = self.column)
+    }
+}
+
+impl std::error::Error for GraphSetParseError {}
+
+/// How strictly [`GraphSetParser`] checks the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParseMode {
+    /// Single space separated tokens, sequential graph ids starting at 0, vertex ids in
+    /// order starting at 0 for each graph. Unknown line types are ignored.
+    #[default]
+    Standard,
+    /// Like [`ParseMode::Standard`], but unknown line types are rejected.
+    Strict,
+    /// Tokens separated by any whitespace, lines starting with `#` are comments and
+    /// `t` lines may omit the `#`. Graph and vertex ids may be sparse and in any order
+    /// (but unique); they are remapped to dense ids and the original ids are kept in
+    /// `Graph::original_id` and `Vertex::original_id`. Unknown line types are ignored.
+    Lenient,
+}
+
+/// Parser for graph databases in the `t`/`v`/`e` line format.
+///
+/// ```no_run
+/// use gspan::parser::GraphSetParser;
+///
+/// let graphs = GraphSetParser::new(false)
+///     .collect_errors(true)
+///     .parse_file("graphs.txt");
+/// ```
+pub struct GraphSetParser {
+    directed: bool,
+    collect_errors: bool,
+    mode: ParseMode,
+}
+
+impl GraphSetParser {
+    pub fn new(directed: bool) -> GraphSetParser {
+        GraphSetParser {
+            directed,
+            collect_errors: false,
+            mode: ParseMode::Standard,
+        }
+    }
+
+    /// How strictly the input is checked (default: [`ParseMode::Standard`]).
+    pub fn mode(mut self, mode: ParseMode) -> GraphSetParser {
+        self.mode = mode;
+        self
+    }
+
+    /// Continue after a malformed line and report all errors at the end instead of
+    /// stopping at the first one (default: false).
+    pub fn collect_errors(mut self, collect_errors: bool) -> GraphSetParser {
+        self.collect_errors = collect_errors;
+        self
+    }
+
+    pub fn parse_file<P: AsRef<Path>>(&self, path: P) -> Result<Vec<Graph>, Error> {
+        let file = File::open(path.as_ref()).map_err(Error::Io)?;
+        self.parse_reader(BufReader::new(file), &path.as_ref().display().to_string())
+    }
+
+    /// Parses a graph database from a reader, `source` is used in error messages.
+    pub fn parse_reader<R: BufRead>(&self, reader: R, source: &str) -> Result<Vec<Graph>, Error> {
+        self.parse(reader, source, None)
+    }
+
+    /// Like [`GraphSetParser::parse_file`], but vertex and edge labels may be arbitrary
+    /// strings (without whitespace). They are interned into `labels`, which can later be
+    /// used to write the patterns with the original labels.
+    pub fn parse_file_with_labels<P: AsRef<Path>>(
+        &self,
+        path: P,
+        labels: &mut LabelDictionary,
+    ) -> Result<Vec<Graph>, Error> {
+        let file = File::open(path.as_ref()).map_err(Error::Io)?;
+        self.parse(
+            BufReader::new(file),
+            &path.as_ref().display().to_string(),
+            Some(labels),
+        )
+    }
+
+    /// Like [`GraphSetParser::parse_reader`], with string labels interned into `labels`.
+    pub fn parse_reader_with_labels<R: BufRead>(
+        &self,
+        reader: R,
+        source: &str,
+        labels: &mut LabelDictionary,
+    ) -> Result<Vec<Graph>, Error> {
+        self.parse(reader, source, Some(labels))
+    }
+
+    fn parse<R: BufRead>(
+        &self,
+        reader: R,
+        source: &str,
+        labels: Option<&mut LabelDictionary>,
+    ) -> Result<Vec<Graph>, Error> {
+        let mut state = ParseState {
+            directed: self.directed,
+            mode: self.mode,
+            labels,
+            graph_list: Vec::new(),
+            current_graph: None,
+            next_graph_id: 0,
+            graph_ids: FxHashSet::default(),
+            vertex_ids: FxHashMap::default(),
+        };
+        let mut errors: Vec<GraphSetParseError> = Vec::new();
+        for (index, line) in reader.lines().enumerate() {
+            let line = line.map_err(Error::Io)?;
+            match state.parse_line(&line) {
+                Ok(LineResult::Continue) => {}
+                Ok(LineResult::Stop) => break,
+                Err((offset, message)) => {
+                    errors.push(GraphSetParseError {
+                        source: source.to_string(),
+                        line: index + 1,
+                        column: line[..offset].chars().count() + 1,
+                        text: line.clone(),
+                        message,
+                    });
+                    if !self.collect_errors {
+                        break;
+                    }
+                }
+            }
+        }
+        if !errors.is_empty() {
+            return Err(Error::Parse(errors));
+        }
+        if let Some(graph) = state.current_graph {
+            state.graph_list.push(graph);
+        }
+        Ok(state.graph_list)
+    }
+}
+
+enum LineResult {
+    Continue,
+    Stop,
+}
+
+/// Byte offset of the offending token and the error message.
+type LineError = (usize, String);
+
+struct ParseState<'d> {
+    directed: bool,
+    mode: ParseMode,
+    /// Dictionary for string labels, integer labels are expected if absent.
+    labels: Option<&'d mut LabelDictionary>,
+    graph_list: Vec<Graph>,
+    current_graph: Option<Graph>,
+    next_graph_id: usize,
+    /// Original graph ids seen so far (lenient mode only).
+    graph_ids: FxHashSet<usize>,
+    /// Original vertex id to vertex index of the current graph (lenient mode only).
+    vertex_ids: FxHashMap<usize, usize>,
+}
+
+impl ParseState<'_> {
+    fn parse_line(&mut self, line: &str) -> Result<LineResult, LineError> {
+        // Blank lines are not a line type, they are skipped in every mode
+        if line.is_empty() {
+            return Ok(LineResult::Continue);
+        }
+        let lenient = self.mode == ParseMode::Lenient;
+        let mut tokens = Tokens::new(line, lenient);
+        let Some((offset, data_type)) = tokens.next() else {
+            return Ok(LineResult::Continue);
+        };
+        if lenient && data_type.starts_with('#') {
+            return Ok(LineResult::Continue);
+        }
+        match data_type {
+            "t" => {
+                let (mut offset, mut id) = tokens.expect("'#' of the graph")?;
+                if !lenient || id == "#" {
+                    (offset, id) = tokens.expect("graph id")?;
+                }
+                if id == "-1" {
+                    return Ok(LineResult::Stop);
+                }
+                if let Some(graph) = self.current_graph.take() {
+                    self.graph_list.push(graph);
+                }
+                let id: usize = parse_token(offset, id, "graph id")?;
+                let expected_id = self.next_graph_id;
+                self.next_graph_id += 1;
+                let mut graph = Graph::new(expected_id, self.directed);
+                graph.original_id = id;
+                self.current_graph = Some(graph);
+                if lenient {
+                    self.vertex_ids.clear();
+                    if !self.graph_ids.insert(id) {
+                        return Err((offset, format!("Duplicate graph id {}", id)));
+                    }
+                } else if id != expected_id {
+                    return Err((
+                        offset,
+                        format!(
+                            "Graph id {} is invalid, expected the id {}",
+                            id, expected_id
+                        ),
+                    ));
+                }
+            }
+            "v" => {
+                let graph = self.current_graph.as_mut().ok_or(missing_graph(offset))?;
+                let (offset, id) = tokens.expect("vertex id")?;
+                let id: usize = parse_token(offset, id, "vertex id")?;
+                if lenient && self.vertex_ids.contains_key(&id) {
+                    return Err((offset, format!("Duplicate vertex id {}", id)));
+                }
+                let vertex = graph.create_vertex();
+                vertex.original_id = id;
+                let expected_id = vertex.id;
+                if lenient {
+                    self.vertex_ids.insert(id, expected_id);
+                } else if id != expected_id {
+                    return Err((
+                        offset,
+                        format!(
+                            "Vertex id {} is invalid, expected the id {}",
+                            id, expected_id
+                        ),
+                    ));
+                }
+                let (offset, label) = tokens.expect("vertex label")?;
+                graph.get_last_vertex().label = match self.labels.as_mut() {
+                    Some(labels) => labels.vertex(label),
+                    None => parse_token(offset, label, "vertex label")?,
+                };
+            }
+            "e" => {
+                let graph = self.current_graph.as_mut().ok_or(missing_graph(offset))?;
+                let (from_offset, from_id) = tokens.expect("from id of the edge")?;
+                let from_id: usize = parse_token(from_offset, from_id, "from id of the edge")?;
+                let (to_offset, to_id) = tokens.expect("to id of the edge")?;
+                let to_id: usize = parse_token(to_offset, to_id, "to id of the edge")?;
+                let (offset, e_label) = tokens.expect("edge label")?;
+                let e_label: usize = match self.labels.as_mut() {
+                    Some(labels) => labels.edge(e_label),
+                    None => parse_token(offset, e_label, "edge label")?,
+                };
+                let vertex_index = |id: usize| {
+                    if lenient {
+                        self.vertex_ids.get(&id).copied()
+                    } else {
+                        Some(id).filter(|id| graph.has_vertex_with_id(id))
+                    }
+                };
+                let Some(from) = vertex_index(from_id) else {
+                    return Err((from_offset, format!("Vertex {} not found", from_id)));
+                };
+                let Some(to) = vertex_index(to_id) else {
+                    return Err((to_offset, format!("Vertex {} not found", to_id)));
+                };
+                graph
+                    .add_edge(from, to, e_label)
+                    .map_err(|err| (offset, err.to_string()))?;
+            }
+            _ => {
+                if self.mode == ParseMode::Strict {
+                    return Err((offset, format!("Unknown line type '{}'", data_type)));
+                }
+            }
+        }
+        Ok(LineResult::Continue)
+    }
+}
+
+fn missing_graph(offset: usize) -> LineError {
+    (
+        offset,
+        "Missing 't' line before the first graph".to_string(),
+    )
+}
+
+/// Tokens of a line with their byte offsets. Tokens are separated by a single space,
+/// or by any run of whitespace if `lenient` is set.
+struct Tokens<'l> {
+    line: &'l str,
+    offset: usize,
+    lenient: bool,
+}
+
+impl<'l> Tokens<'l> {
+    fn new(line: &'l str, lenient: bool) -> Tokens<'l> {
+        Tokens {
+            line,
+            offset: 0,
+            lenient,
+        }
+    }
+
+    fn next(&mut self) -> Option<(usize, &'l str)> {
+        if self.lenient {
+            let rest = &self.line[self.offset.min(self.line.len())..];
+            let start = self.line.len() - rest.trim_start().len();
+            if start == self.line.len() {
+                self.offset = self.line.len();
+                return None;
+            }
+            let end = self.line[start..]
+                .find(char::is_whitespace)
+                .map_or(self.line.len(), |i| start + i);
+            self.offset = end;
+            return Some((start, &self.line[start..end]));
+        }
+        if self.offset > self.line.len() {
+            return None;
+        }
+        let start = self.offset;
+        let end = self.line[start..]
+            .find(' ')
+            .map_or(self.line.len(), |i| start + i);
+        self.offset = end + 1;
+        Some((start, &self.line[start..end]))
+    }
+
+    fn expect(&mut self, what: &str) -> Result<(usize, &'l str), LineError> {
+        self.next()
+            .ok_or((self.line.len(), format!("Missing {}", what)))
+    }
+}
+
+fn parse_token<T: FromStr>(offset: usize, token: &str, what: &str) -> Result<T, LineError> {
+    token
+        .parse::<T>()
+        .map_err(|_| (offset, format!("Invalid {} '{}'", what, token)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str, collect_errors: bool) -> Result<Vec<Graph>, Error> {
+        GraphSetParser::new(false)
+            .collect_errors(collect_errors)
+            .parse_reader(input.as_bytes(), "test")
+    }
+
+    #[test]
+    fn test_parse() {
+        let graphs = parse(
+            "t # 0\nv 0 1\nv 1 2\ne 0 1 3\nt # 1\nv 0 1\nt # -1\n",
+            false,
+        )
+        .unwrap();
+        assert_eq!(graphs.len(), 2);
+        assert_eq!(graphs[0].vertices.len(), 2);
+        assert_eq!(graphs[0].vertices[1].edges[0].to, 0);
+        assert_eq!(graphs[1].vertices[0].label, 1);
+    }
+
+    #[test]
+    fn test_error_position() {
+        let Err(Error::Parse(errors)) = parse("t # 0\nv 0 1\nv 1 2\ne 0 1 x\n", false) else {
+            panic!("expected a parse error");
+        };
+        assert_eq!(errors.len(), 1);
+        assert_eq!((errors[0].line, errors[0].column), (4, 7));
+        assert_eq!(errors[0].text, "e 0 1 x");
+        assert_eq!(
+            errors[0].to_string(),
+            "test:4:7: Invalid edge label 'x'\n    e 0 1 x\n          ^"
+        );
+    }
+
+    #[test]
+    fn test_collect_errors() {
+        let input = "t # 0\nv 0 a\nv 1 2\ne 0 5 1\nt # 1\n";
+        let Err(Error::Parse(errors)) = parse(input, true) else {
+            panic!("expected a parse error");
+        };
+        let positions: Vec<(usize, usize)> = errors.iter().map(|e| (e.line, e.column)).collect();
+        assert_eq!(positions, vec![(2, 5), (4, 5)]);
+    }
+
+    #[test]
+    fn test_lenient() {
+        let input = "# comment\nt # 7\nv 10\t1\nv  3 2\ne 3 10 1  # trailing\nt 2\nv 0 1\n";
+        let graphs = GraphSetParser::new(false)
+            .mode(ParseMode::Lenient)
+            .parse_reader(input.as_bytes(), "test")
+            .unwrap();
+        assert_eq!(graphs.len(), 2);
+        assert_eq!((graphs[0].id, graphs[0].original_id), (0, 7));
+        assert_eq!((graphs[1].id, graphs[1].original_id), (1, 2));
+        let vertex = &graphs[0].vertices[1];
+        assert_eq!((vertex.id, vertex.original_id, vertex.label), (1, 3, 2));
+        assert_eq!(vertex.edges[0].to, 0);
+
+        let Err(Error::Parse(errors)) = GraphSetParser::new(false)
+            .mode(ParseMode::Lenient)
+            .parse_reader("t # 1\nv 4 1\nv 4 1\nt # 1\n".as_bytes(), "test")
+        else {
+            panic!("expected a parse error");
+        };
+        assert_eq!(errors[0].message, "Duplicate vertex id 4");
+    }
+
+    #[test]
+    fn test_string_labels() {
+        let input = "t # 0\nv 0 C\nv 1 N\nv 2 C\ne 0 1 single\ne 1 2 double\n";
+        let mut labels = LabelDictionary::new();
+        let graphs = GraphSetParser::new(false)
+            .parse_reader_with_labels(input.as_bytes(), "test", &mut labels)
+            .unwrap();
+        let vertex_labels: Vec<isize> = graphs[0].vertices.iter().map(|v| v.label).collect();
+        assert_eq!(vertex_labels, vec![0, 1, 0]);
+        assert_eq!(graphs[0].vertices[1].edges[1].e_label, 1);
+        assert_eq!(
+            graphs[0].to_str_repr_with_labels(None, &labels),
+            "t # 0\nv 0 C\nv 1 N\nv 2 C\ne 0 1 single\ne 1 0 single\ne 1 2 double\ne 2 1 double"
+        );
+    }
+
+    #[test]
+    fn test_strict() {
+        let blank = "t # 0\nv 0 1\n\nv 1 2\n";
+        let graphs = GraphSetParser::new(false)
+            .mode(ParseMode::Strict)
+            .parse_reader(blank.as_bytes(), "test")
+            .unwrap();
+        assert_eq!(graphs[0].vertices.len(), 2);
+        let input = "t # 0\nv 0 1\nx 1 2\n";
+        assert!(parse(input, false).is_ok());
+        let Err(Error::Parse(errors)) = GraphSetParser::new(false)
+            .mode(ParseMode::Strict)
+            .parse_reader(input.as_bytes(), "test")
+        else {
+            panic!("expected a parse error");
+        };
+        assert_eq!((errors[0].line, errors[0].column), (3, 1));
+    }
+}
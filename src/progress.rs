@@ -0,0 +1,22 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+/// State of a running search, passed to the progress hook set with
+/// [`crate::GSpanConfigBuilder::progress`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Progress {
+    /// Number of the root edge (the first edge of the DFS codes) searched currently,
+    /// starting at 1.
+    pub root: usize,
+    /// Number of frequent root edges.
+    pub roots: usize,
+    /// Number of edges of the pattern searched currently.
+    pub depth: usize,
+    /// Number of patterns reported so far.
+    pub patterns: usize,
+    /// Time since the search started.
+    pub elapsed: Duration,
+}
+
+/// Called after every visited one edge extension, so it should return quickly.
+pub(crate) type ProgressHook = Arc<dyn Fn(&Progress) + Send + Sync>;
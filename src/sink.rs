@@ -0,0 +1,212 @@
+use crate::models::labels::LabelDictionary;
+use crate::models::pattern::Pattern;
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::mpsc::Sender;
+
+/// Receives the frequent patterns found by [`crate::GSpanConfig::run_with_sink`].
+///
+/// Implement this trait to write patterns in a custom format, count or filter
+/// them, or to pass them on to another thread.
+///
+/// Each [`Pattern`] carries its DFS code, support and the ids of the graphs containing
+/// it. Its occurrences (`Pattern::embeddings`) are only collected if enabled with
+/// [`crate::GSpanConfigBuilder::embeddings`], as they take time and memory for every
+/// pattern; otherwise they are empty. They can also be found afterwards with
+/// [`crate::find_matches`].
+pub trait PatternSink {
+    /// Called once for every reported pattern.
+    fn report(&mut self, pattern: Pattern) -> io::Result<()>;
+
+    /// Called once after the last pattern was reported.
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Writes the patterns in the text format of the input graph database.
+pub struct TextSink<W: Write> {
+    out: W,
+    labels: Option<LabelDictionary>,
+    graph_ids: bool,
+}
+
+impl TextSink<BufWriter<File>> {
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<TextSink<BufWriter<File>>> {
+        Ok(TextSink::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W: Write> TextSink<W> {
+    pub fn new(out: W) -> TextSink<W> {
+        TextSink {
+            out,
+            labels: None,
+            graph_ids: false,
+        }
+    }
+
+    /// Writes the string labels of the dictionary instead of the integer labels.
+    pub fn with_labels(mut self, labels: LabelDictionary) -> TextSink<W> {
+        self.labels = Some(labels);
+        self
+    }
+
+    /// Writes an `x: <graph ids>` line after each pattern with the original ids of the
+    /// graphs containing it, like the original gSpan implementation.
+    pub fn with_graph_ids(mut self, graph_ids: bool) -> TextSink<W> {
+        self.graph_ids = graph_ids;
+        self
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+impl<W: Write> PatternSink for TextSink<W> {
+    fn report(&mut self, pattern: Pattern) -> io::Result<()> {
+        let repr = match &self.labels {
+            Some(labels) => pattern.to_str_repr_with_labels(labels),
+            None => pattern.to_str_repr(),
+        };
+        self.out.write_all(&repr.into_bytes())?;
+        self.out.write_all(b"\n")?;
+        if self.graph_ids {
+            write!(self.out, "x:")?;
+            for id in &pattern.graph_ids {
+                write!(self.out, " {}", id)?;
+            }
+            self.out.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// Writes the embeddings of the patterns (see [`Pattern::embeddings`]) in the format of
+/// the `match` command: a `t # <pattern id> * <support>` line per pattern, followed by
+/// one `m <graph id> <vertex ids>` line per embedding.
+pub struct EmbeddingSink<W: Write> {
+    out: W,
+}
+
+impl EmbeddingSink<BufWriter<File>> {
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<EmbeddingSink<BufWriter<File>>> {
+        Ok(EmbeddingSink::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W: Write> EmbeddingSink<W> {
+    pub fn new(out: W) -> EmbeddingSink<W> {
+        EmbeddingSink { out }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+impl<W: Write> PatternSink for EmbeddingSink<W> {
+    fn report(&mut self, pattern: Pattern) -> io::Result<()> {
+        writeln!(self.out, "t # {} * {}", pattern.graph.id, pattern.support)?;
+        for found in &pattern.embeddings {
+            for mapping in &found.mappings {
+                write!(self.out, "m {}", found.graph_id)?;
+                for v in mapping {
+                    write!(self.out, " {}", v)?;
+                }
+                self.out.write_all(b"\n")?;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// Reports every pattern to both sinks, e.g. to write the patterns and their embeddings.
+impl<A: PatternSink, B: PatternSink> PatternSink for (A, B) {
+    fn report(&mut self, pattern: Pattern) -> io::Result<()> {
+        self.0.report(pattern.clone())?;
+        self.1.report(pattern)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.0.finish()?;
+        self.1.finish()
+    }
+}
+
+impl PatternSink for Vec<Pattern> {
+    fn report(&mut self, pattern: Pattern) -> io::Result<()> {
+        self.push(pattern);
+        Ok(())
+    }
+}
+
+impl PatternSink for Sender<Pattern> {
+    fn report(&mut self, pattern: Pattern) -> io::Result<()> {
+        self.send(pattern)
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "Pattern receiver dropped"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matcher::Match;
+    use crate::models::dfs_code::DFSCode;
+    use crate::models::graph::Graph;
+
+    #[test]
+    fn test_text_sink() {
+        let mut g = Graph::new(3, false);
+        g.create_vertex().label = 7;
+        let mut sink = TextSink::new(Vec::new());
+        sink.report(Pattern::new(g, DFSCode::new(), 2, vec![0, 1]))
+            .unwrap();
+        sink.finish().unwrap();
+        assert_eq!(sink.into_inner(), b"t # 3 * 2\nv 0 7\n");
+    }
+
+    #[test]
+    fn test_text_sink_graph_ids() {
+        let mut g = Graph::new(3, false);
+        g.create_vertex().label = 7;
+        let mut sink = TextSink::new(Vec::new()).with_graph_ids(true);
+        sink.report(Pattern::new(g, DFSCode::new(), 2, vec![0, 4]))
+            .unwrap();
+        assert_eq!(sink.into_inner(), b"t # 3 * 2\nv 0 7\nx: 0 4\n");
+    }
+
+    #[test]
+    fn test_embedding_sink() {
+        let mut pattern = Pattern::new(Graph::new(3, false), DFSCode::new(), 2, vec![0, 4]);
+        pattern.embeddings = vec![
+            Match {
+                graph_id: 0,
+                mappings: vec![vec![1, 0], vec![1, 2]],
+            },
+            Match {
+                graph_id: 4,
+                mappings: vec![vec![5, 6]],
+            },
+        ];
+        let mut sink = (Vec::new(), EmbeddingSink::new(Vec::new()));
+        sink.report(pattern).unwrap();
+        sink.finish().unwrap();
+        assert_eq!(sink.0.len(), 1);
+        assert_eq!(
+            String::from_utf8(sink.1.into_inner()).unwrap(),
+            "t # 3 * 2\nm 0 1 0\nm 0 1 2\nm 4 5 6\n"
+        );
+    }
+}
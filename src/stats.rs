@@ -0,0 +1,109 @@
+use crate::models::pattern::Pattern;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::Duration;
+
+/// Counters of a mining run, see [`crate::RunSummary::stats`] and
+/// [`crate::Patterns::stats`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stats {
+    /// Number of reported patterns by number of vertices.
+    pub patterns_by_vertices: BTreeMap<usize, usize>,
+    /// Number of reported patterns by number of edges.
+    pub patterns_by_edges: BTreeMap<usize, usize>,
+    /// Number of minimality checks of frequent DFS codes.
+    pub is_min_checks: usize,
+    /// Number of minimality checks which found the DFS code not to be minimal.
+    pub is_min_failed: usize,
+    /// Number of visited DFS codes, i.e. root edges and one edge extensions.
+    pub extensions_tried: usize,
+    /// Number of visited DFS codes which were frequent.
+    pub extensions_frequent: usize,
+    /// Largest number of embeddings of a visited DFS code.
+    pub peak_projections: usize,
+    /// Number of patterns in closed mode whose extensions were not searched, as none of
+    /// them can be closed.
+    pub early_terminations: usize,
+    /// Time spent in minimality checks.
+    pub min_check_time: Duration,
+    /// Time spent enumerating the one edge extensions of frequent minimal DFS codes.
+    pub extension_time: Duration,
+}
+
+impl Stats {
+    /// Total number of reported patterns.
+    pub fn patterns(&self) -> usize {
+        self.patterns_by_vertices.values().sum()
+    }
+
+    pub(crate) fn record_pattern(&mut self, pattern: &Pattern) {
+        let (vertices, edges) = if pattern.dfs_code.dfs_vec.is_empty() {
+            (pattern.graph.vertices.len(), 0)
+        } else {
+            (
+                pattern.dfs_code.count_node(),
+                pattern.dfs_code.dfs_vec.len(),
+            )
+        };
+        *self.patterns_by_vertices.entry(vertices).or_default() += 1;
+        *self.patterns_by_edges.entry(edges).or_default() += 1;
+    }
+
+    /// Returns the counters as a JSON object, durations are given in seconds.
+    pub fn to_json(&self) -> String {
+        let counts = |map: &BTreeMap<usize, usize>| {
+            let entries: Vec<String> = map
+                .iter()
+                .map(|(size, count)| format!("\"{}\": {}", size, count))
+                .collect();
+            format!("{{{}}}", entries.join(", "))
+        };
+        let mut json = String::from("{\n");
+        let fields = [
+            ("patterns", self.patterns().to_string()),
+            ("patterns_by_vertices", counts(&self.patterns_by_vertices)),
+            ("patterns_by_edges", counts(&self.patterns_by_edges)),
+            ("is_min_checks", self.is_min_checks.to_string()),
+            ("is_min_failed", self.is_min_failed.to_string()),
+            ("extensions_tried", self.extensions_tried.to_string()),
+            ("extensions_frequent", self.extensions_frequent.to_string()),
+            ("peak_projections", self.peak_projections.to_string()),
+            ("early_terminations", self.early_terminations.to_string()),
+            (
+                "min_check_seconds",
+                self.min_check_time.as_secs_f64().to_string(),
+            ),
+            (
+                "extension_seconds",
+                self.extension_time.as_secs_f64().to_string(),
+            ),
+        ];
+        for (i, (name, value)) in fields.iter().enumerate() {
+            let separator = if i + 1 < fields.len() { "," } else { "" };
+            let _ = writeln!(json, "  \"{}\": {}{}", name, value, separator);
+        }
+        json.push('}');
+        json
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_json() {
+        let mut stats = Stats::default();
+        stats.patterns_by_vertices.insert(1, 2);
+        stats.patterns_by_vertices.insert(2, 1);
+        stats.patterns_by_edges.insert(0, 2);
+        stats.patterns_by_edges.insert(1, 1);
+        stats.min_check_time = Duration::from_millis(1500);
+        let json = stats.to_json();
+        assert!(json.starts_with("{\n  \"patterns\": 3,\n"));
+        assert!(json.contains("  \"patterns_by_vertices\": {\"1\": 2, \"2\": 1},\n"));
+        assert!(json.contains("  \"min_check_seconds\": 1.5,\n"));
+        assert!(json.ends_with("  \"extension_seconds\": 0\n}"));
+    }
+}